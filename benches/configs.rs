@@ -7,7 +7,6 @@ use ray_tracing::RunConfig;
 
 fn get_config() -> RunConfig<'static> {
     let mut config = RunConfig::default();
-    config.quiet = true;
 
     config.img_config.aspect_ratio = 3.0 / 2.0;
     config.img_config.width = 150;
@@ -31,13 +30,13 @@ pub fn samples_per_pixel(c: &mut Criterion) {
 
     config.img_config.samples_per_pixel = 1;
     group.bench_with_input(BenchmarkId::from_parameter(1), &config, |b, c| {
-        b.iter(|| ray_tracing::run(&c))
+        b.iter(|| ray_tracing::render(c))
     });
 
     for i in (10..=100).step_by(20) {
         config.img_config.samples_per_pixel = i;
         group.bench_with_input(BenchmarkId::from_parameter(i), &config, |b, c| {
-            b.iter(|| ray_tracing::run(&c))
+            b.iter(|| ray_tracing::render(c))
         });
     }
 
@@ -52,13 +51,13 @@ pub fn ray_depth(c: &mut Criterion) {
 
     config.img_config.max_depth = 1;
     group.bench_with_input(BenchmarkId::from_parameter(1), &config, |b, c| {
-        b.iter(|| ray_tracing::run(&c))
+        b.iter(|| ray_tracing::render(c))
     });
 
     for i in (5..=50).step_by(5) {
         config.img_config.max_depth = i;
         group.bench_with_input(BenchmarkId::from_parameter(i), &config, |b, c| {
-            b.iter(|| ray_tracing::run(&c))
+            b.iter(|| ray_tracing::render(c))
         });
     }
 
@@ -76,7 +75,7 @@ pub fn img_width(c: &mut Criterion) {
     for i in (50..=500).step_by(50) {
         config.img_config.width = i;
         group.bench_with_input(BenchmarkId::from_parameter(i), &config, |b, c| {
-            b.iter(|| ray_tracing::run(&c))
+            b.iter(|| ray_tracing::render(c))
         });
     }
 
@@ -94,7 +93,7 @@ pub fn sphere_count(c: &mut Criterion) {
     for i in (50..=400).step_by(50) {
         config.scene_config.small_sphere_count = i;
         group.bench_with_input(BenchmarkId::from_parameter(i), &config, |b, c| {
-            b.iter(|| ray_tracing::run(&c))
+            b.iter(|| ray_tracing::render(c))
         });
     }
 
@@ -114,19 +113,19 @@ pub fn materials(c: &mut Criterion) {
         config.scene_config.diffuse_prob = leading_prob;
         config.scene_config.metal_prob = other_probs;
         group.bench_with_input(BenchmarkId::new("Diffuse", i), &config, |b, c| {
-            b.iter(|| ray_tracing::run(&c))
+            b.iter(|| ray_tracing::render(c))
         });
 
         config.scene_config.metal_prob = leading_prob;
         config.scene_config.diffuse_prob = other_probs;
         group.bench_with_input(BenchmarkId::new("Metal", i), &config, |b, c| {
-            b.iter(|| ray_tracing::run(&c))
+            b.iter(|| ray_tracing::render(c))
         });
 
         config.scene_config.diffuse_prob = other_probs;
         config.scene_config.metal_prob = other_probs;
         group.bench_with_input(BenchmarkId::new("Glass", i), &config, |b, c| {
-            b.iter(|| ray_tracing::run(&c))
+            b.iter(|| ray_tracing::render(c))
         });
     }
 
@@ -146,12 +145,12 @@ pub fn bvh(c: &mut Criterion) {
 
         config.use_bvh = true;
         group.bench_with_input(BenchmarkId::new("bvh", i), &config, |b, c| {
-            b.iter(|| ray_tracing::run(&c))
+            b.iter(|| ray_tracing::render(c))
         });
 
         config.use_bvh = false;
         group.bench_with_input(BenchmarkId::new("plain", i), &config, |b, c| {
-            b.iter(|| ray_tracing::run(&c))
+            b.iter(|| ray_tracing::render(c))
         });
     }
 
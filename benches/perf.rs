@@ -6,10 +6,11 @@ use std::time::Duration;
 use ray_tracing::RunConfig;
 
 fn get_config() -> RunConfig<'static> {
-    let mut config = RunConfig::default();
+    let mut config = RunConfig {
+        use_bvh: false,
+        ..Default::default()
+    };
     config.img_config.aspect_ratio = 3.0 / 2.0;
-    config.quiet = true;
-    config.use_bvh = false;
 
     config
 }
@@ -28,7 +29,7 @@ pub fn small(c: &mut Criterion) {
     let mut config = get_config();
     config.img_config.width = 100;
 
-    group.bench_function("Small", |b| b.iter(|| ray_tracing::run(&config)));
+    group.bench_function("Small", |b| b.iter(|| ray_tracing::render(&config)));
 
     group.finish();
 }
@@ -40,7 +41,7 @@ pub fn medium(c: &mut Criterion) {
     let mut config = get_config();
     config.img_config.width = 200;
 
-    group.bench_function("Meduim", |b| b.iter(|| ray_tracing::run(&config)));
+    group.bench_function("Meduim", |b| b.iter(|| ray_tracing::render(&config)));
 
     group.finish();
 }
@@ -52,7 +53,7 @@ pub fn large(c: &mut Criterion) {
     let mut config = get_config();
     config.img_config.width = 500;
 
-    group.bench_function("Large", |b| b.iter(|| ray_tracing::run(&config)));
+    group.bench_function("Large", |b| b.iter(|| ray_tracing::render(&config)));
 
     group.finish();
 }
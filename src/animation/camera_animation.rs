@@ -0,0 +1,80 @@
+use super::Track;
+use crate::{CameraConfig, Point, Vec3};
+
+/// Keyframes for the fields of a `CameraConfig`. Fields without a track
+/// keep the value from the config.
+#[derive(Clone, Default)]
+pub struct CameraAnimation {
+    pub lookfrom: Option<Track<Point>>,
+    pub lookat: Option<Track<Point>>,
+    pub vec_up: Option<Track<Vec3>>,
+    pub vert_fov: Option<Track<f64>>,
+    pub aperture: Option<Track<f64>>,
+    pub focus_dist: Option<Track<f64>>,
+}
+
+impl CameraAnimation {
+    pub fn is_static(&self) -> bool {
+        self.lookfrom.is_none()
+            && self.lookat.is_none()
+            && self.vec_up.is_none()
+            && self.vert_fov.is_none()
+            && self.aperture.is_none()
+            && self.focus_dist.is_none()
+    }
+
+    /// `config` with the animated fields set to their value at `time`.
+    pub fn apply(&self, config: &CameraConfig, time: f64) -> CameraConfig {
+        let mut config = config.clone();
+
+        if let Some(track) = &self.lookfrom {
+            config.lookfrom = track.sample(time);
+        }
+        if let Some(track) = &self.lookat {
+            config.lookat = track.sample(time);
+        }
+        if let Some(track) = &self.vec_up {
+            config.vec_up = track.sample(time);
+        }
+        if let Some(track) = &self.vert_fov {
+            config.vert_fov = track.sample(time);
+        }
+        if let Some(track) = &self.aperture {
+            config.aperture = track.sample(time);
+        }
+        if let Some(track) = &self.focus_dist {
+            config.focus_dist = track.sample(time);
+        }
+
+        config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_animated_fields_change() {
+        let animation = CameraAnimation {
+            lookfrom: Some(
+                Track::linear(vec![
+                    (0.0, Point::new(0.0, 0.0, 0.0)),
+                    (2.0, Point::new(4.0, 0.0, 0.0)),
+                ])
+                .unwrap(),
+            ),
+            vert_fov: Some(Track::constant(45.0)),
+            ..CameraAnimation::default()
+        };
+        let base = CameraConfig::default();
+
+        let config = animation.apply(&base, 1.0);
+        assert!(config.lookfrom.approx_eq(Point::new(2.0, 0.0, 0.0)));
+        assert!(float_eq!(config.vert_fov, 45.0));
+        assert!(config.lookat.approx_eq(base.lookat));
+        assert!(float_eq!(config.focus_dist, base.focus_dist));
+        assert!(!animation.is_static());
+        assert!(CameraAnimation::default().is_static());
+    }
+}
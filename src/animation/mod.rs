@@ -0,0 +1,5 @@
+mod camera_animation;
+mod track;
+
+pub use camera_animation::CameraAnimation;
+pub use track::{Interpolation, Track};
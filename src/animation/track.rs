@@ -0,0 +1,146 @@
+use std::ops::{Add, Mul};
+
+use crate::{Error, Result};
+
+/// How values are computed between keyframes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    /// Catmull-Rom spline through the keyframes, for smooth motion.
+    Spline,
+}
+
+/// Value changing over time, given by keyframes. Before the first keyframe
+/// and after the last one the value stays constant.
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    /// Time in seconds and value, sorted by time.
+    keys: Vec<(f64, T)>,
+    interpolation: Interpolation,
+}
+
+impl<T> Track<T>
+where
+    T: Copy + Add<Output = T> + Mul<f64, Output = T>,
+{
+    /// Fails without keyframes, or with a keyframe time that isn't finite.
+    pub fn new(interpolation: Interpolation, mut keys: Vec<(f64, T)>) -> Result<Self> {
+        if keys.is_empty() {
+            return Err(Error::InvalidConfig(
+                "A track needs at least one keyframe".to_string(),
+            ));
+        }
+        if let Some(&(time, _)) = keys.iter().find(|k| !k.0.is_finite()) {
+            return Err(Error::InvalidConfig(format!(
+                "Keyframe times must be finite, found {}",
+                time
+            )));
+        }
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        Ok(Self {
+            keys,
+            interpolation,
+        })
+    }
+
+    pub fn linear(keys: Vec<(f64, T)>) -> Result<Self> {
+        Self::new(Interpolation::Linear, keys)
+    }
+
+    pub fn spline(keys: Vec<(f64, T)>) -> Result<Self> {
+        Self::new(Interpolation::Spline, keys)
+    }
+
+    /// Same value at any time.
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    /// Times of the first and last keyframes.
+    pub fn range(&self) -> (f64, f64) {
+        (self.keys[0].0, self.keys[self.keys.len() - 1].0)
+    }
+
+    pub fn times(&self) -> impl Iterator<Item = f64> + '_ {
+        self.keys.iter().map(|k| k.0)
+    }
+
+    pub fn sample(&self, time: f64) -> T {
+        let last = self.keys.len() - 1;
+        if time <= self.keys[0].0 {
+            return self.keys[0].1;
+        }
+        if time >= self.keys[last].0 {
+            return self.keys[last].1;
+        }
+
+        // Segment between keys i and i + 1
+        let i = self.keys.partition_point(|k| k.0 <= time) - 1;
+        let (t1, p1) = self.keys[i];
+        let (t2, p2) = self.keys[i + 1];
+        let t = (time - t1) / (t2 - t1);
+
+        match self.interpolation {
+            Interpolation::Linear => p1 * (1.0 - t) + p2 * t,
+            Interpolation::Spline => {
+                // The ends are repeated so the curve still reaches them
+                let p0 = self.keys[i.saturating_sub(1)].1;
+                let p3 = self.keys[(i + 2).min(last)].1;
+
+                let t2 = t * t;
+                let t3 = t2 * t;
+                p0 * (0.5 * (-t3 + 2.0 * t2 - t))
+                    + p1 * (0.5 * (3.0 * t3 - 5.0 * t2 + 2.0))
+                    + p2 * (0.5 * (-3.0 * t3 + 4.0 * t2 + t))
+                    + p3 * (0.5 * (t3 - t2))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Vec3;
+
+    #[test]
+    fn linear() {
+        let track = Track::linear(vec![(1.0, 0.0), (0.0, 2.0), (3.0, 4.0)]).unwrap();
+
+        assert!(float_eq!(track.sample(-1.0), 2.0));
+        assert!(float_eq!(track.sample(0.5), 1.0));
+        assert!(float_eq!(track.sample(2.0), 2.0));
+        assert!(float_eq!(track.sample(5.0), 4.0));
+        assert_eq!(track.range(), (0.0, 3.0));
+    }
+
+    #[test]
+    fn spline_through_keys() {
+        let keys = vec![
+            (0.0, Vec3::new(0.0, 0.0, 0.0)),
+            (1.0, Vec3::new(1.0, 2.0, 0.0)),
+            (2.0, Vec3::new(2.0, 0.0, 1.0)),
+            (4.0, Vec3::new(0.0, 1.0, 3.0)),
+        ];
+        let track = Track::spline(keys.clone()).unwrap();
+
+        for (time, value) in keys {
+            assert!(track.sample(time).approx_eq_epsilon(value, 1e-12));
+        }
+        // Smooth around the peak at t = 1, unlike the linear one
+        let linear = Track::linear(vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)]).unwrap();
+        let spline = Track::spline(vec![(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)]).unwrap();
+        assert!(spline.sample(0.9) > linear.sample(0.9));
+        assert!(float_eq!(spline.sample(0.9), spline.sample(1.1), 1e-12));
+    }
+
+    #[test]
+    fn bad_keys() {
+        assert!(Track::<f64>::linear(Vec::new()).is_err());
+        assert!(Track::spline(vec![(0.0, 1.0), (f64::NAN, 2.0)]).is_err());
+    }
+}
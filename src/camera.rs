@@ -1,59 +0,0 @@
-use crate::{Point, Ray, Vec3};
-
-pub struct Camera {
-    origin: Point,
-    lower_left: Point,
-    horizontal: Vec3,
-    vertical: Vec3,
-    u: Vec3,
-    v: Vec3,
-    lens_radius: f64,
-}
-
-impl Camera {
-    pub fn new(
-        lookfrom: Point,
-        lookat: Point,
-        vup: Vec3,
-        vert_fov: f64,
-        aspect_ratio: f64,
-        aperture: f64,
-        focus_dist: f64,
-    ) -> Self {
-        let theta = vert_fov.to_radians();
-        let h = (theta / 2.0).tan();
-
-        let viewport_height = 2.0 * h;
-        let viewport_width = aspect_ratio * viewport_height;
-
-        let w = (lookfrom - lookat).unit_vector();
-        let u = vup.cross(w).unit_vector();
-        let v = w.cross(u);
-
-        let origin = lookfrom;
-        let horizontal = u * viewport_width * focus_dist;
-        let vertical = v * viewport_height * focus_dist;
-        let lower_left = origin - horizontal / 2.0 - vertical / 2.0 - w * focus_dist;
-
-        let lens_radius = aperture / 2.0;
-        Self {
-            origin,
-            lower_left,
-            horizontal,
-            vertical,
-            u,
-            v,
-            lens_radius,
-        }
-    }
-
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = Vec3::random_in_unit_disk() * self.lens_radius;
-        let offset = self.u * rd.x() + self.v * rd.y();
-
-        Ray::new(
-            self.origin + offset,
-            self.lower_left + self.horizontal * s + self.vertical * t - self.origin - offset,
-        )
-    }
-}
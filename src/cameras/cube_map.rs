@@ -0,0 +1,85 @@
+use super::{view_frame, Camera};
+use crate::{Point, Ray, Vec3};
+
+/// Six 90 degree views around the camera laid out in a 3x2 grid:
+///
+/// ```text
+/// +------+-------+-------+
+/// | left | front | right |
+/// +------+-------+-------+
+/// | back |  up   | down  |
+/// +------+-------+-------+
+/// ```
+///
+/// The side faces keep `vec_up` pointing up, the up face has the front at
+/// its bottom edge and the down face has it at its top edge.
+pub struct CubeMapCamera {
+    origin: Point,
+    /// Forward, right and up vectors of each face, in layout order.
+    faces: [(Vec3, Vec3, Vec3); 6],
+}
+
+impl CubeMapCamera {
+    pub fn new(lookfrom: Point, lookat: Point, vup: Vec3) -> Self {
+        let (u, v, w) = view_frame(lookfrom, lookat, vup);
+
+        Self {
+            origin: lookfrom,
+            faces: [
+                (-u, -w, v),
+                (-w, u, v),
+                (u, w, v),
+                (w, -u, v),
+                (v, u, w),
+                (-v, u, -w),
+            ],
+        }
+    }
+}
+
+impl Camera for CubeMapCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let x = s * 3.0;
+        let y = (1.0 - t) * 2.0;
+        let column = (x as usize).min(2);
+        let row = (y as usize).min(1);
+
+        let a = 2.0 * (x - column as f64) - 1.0;
+        let b = 1.0 - 2.0 * (y - row as f64);
+        let (forward, right, up) = self.faces[row * 3 + column];
+
+        Some(Ray::new(self.origin, forward + right * a + up * b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn face_centers() {
+        let camera = CubeMapCamera::new(
+            Point::ceros(),
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let dir = |s: f64, t: f64| camera.get_ray(s, t).unwrap().direction().unit_vector();
+
+        let expected = [
+            (1.0 / 6.0, 0.75, Vec3::new(-1.0, 0.0, 0.0)),
+            (0.5, 0.75, Vec3::new(0.0, 0.0, -1.0)),
+            (5.0 / 6.0, 0.75, Vec3::new(1.0, 0.0, 0.0)),
+            (1.0 / 6.0, 0.25, Vec3::new(0.0, 0.0, 1.0)),
+            (0.5, 0.25, Vec3::new(0.0, 1.0, 0.0)),
+            (5.0 / 6.0, 0.25, Vec3::new(0.0, -1.0, 0.0)),
+        ];
+        for &(s, t, d) in expected.iter() {
+            assert!(dir(s, t).approx_eq_epsilon(d, 1e-12));
+        }
+
+        // Edges of neighbouring side faces meet
+        assert!(dir(1.0 / 3.0 - 1e-9, 0.75).approx_eq_epsilon(dir(1.0 / 3.0 + 1e-9, 0.75), 1e-6));
+        // The bottom of the up face continues the top of the front face
+        assert!(dir(0.5, 0.0).approx_eq_epsilon(dir(0.5, 1.0), 1e-6));
+    }
+}
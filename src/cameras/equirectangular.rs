@@ -0,0 +1,64 @@
+use std::f64::consts::PI;
+
+use super::{view_frame, Camera};
+use crate::{Point, Ray, Vec3};
+
+/// 360 degree panorama. The horizontal axis is the longitude, with the
+/// direction towards `lookat` in the middle, and the vertical one the
+/// latitude.
+pub struct EquirectangularCamera {
+    origin: Point,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl EquirectangularCamera {
+    pub fn new(lookfrom: Point, lookat: Point, vup: Vec3) -> Self {
+        let (u, v, w) = view_frame(lookfrom, lookat, vup);
+
+        Self {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+        }
+    }
+
+    /// Direction for a longitude and latitude in radians.
+    pub(crate) fn direction(&self, longitude: f64, latitude: f64) -> Vec3 {
+        let (sin_lon, cos_lon) = longitude.sin_cos();
+        let (sin_lat, cos_lat) = latitude.sin_cos();
+
+        -self.w * (cos_lat * cos_lon) + self.u * (cos_lat * sin_lon) + self.v * sin_lat
+    }
+}
+
+impl Camera for EquirectangularCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let longitude = (s - 0.5) * 2.0 * PI;
+        let latitude = (t - 0.5) * PI;
+
+        Some(Ray::new(self.origin, self.direction(longitude, latitude)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directions() {
+        let camera = EquirectangularCamera::new(
+            Point::ceros(),
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let dir = |s, t| camera.get_ray(s, t).unwrap().direction();
+
+        assert!(dir(0.5, 0.5).approx_eq_epsilon(Vec3::new(0.0, 0.0, -1.0), 1e-12));
+        assert!(dir(0.75, 0.5).approx_eq_epsilon(Vec3::new(1.0, 0.0, 0.0), 1e-12));
+        assert!(dir(0.0, 0.5).approx_eq_epsilon(Vec3::new(0.0, 0.0, 1.0), 1e-12));
+        assert!(dir(0.3, 1.0).approx_eq_epsilon(Vec3::new(0.0, 1.0, 0.0), 1e-12));
+    }
+}
@@ -0,0 +1,125 @@
+use super::{view_frame, Camera};
+use crate::{Point, Ray, Vec3};
+
+/// How the angle to the optical axis maps to the distance from the center of
+/// the image circle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FisheyeMapping {
+    /// Distance proportional to the angle.
+    Equidistant,
+    /// Equal areas of the image cover equal solid angles.
+    Equisolid,
+}
+
+pub struct FisheyeCamera {
+    origin: Point,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    half_fov: f64,
+    aspect_ratio: f64,
+    mapping: FisheyeMapping,
+}
+
+impl FisheyeCamera {
+    pub fn new(
+        lookfrom: Point,
+        lookat: Point,
+        vup: Vec3,
+        fov: f64,
+        aspect_ratio: f64,
+        mapping: FisheyeMapping,
+    ) -> Self {
+        let (u, v, w) = view_frame(lookfrom, lookat, vup);
+
+        Self {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            half_fov: fov.to_radians() / 2.0,
+            aspect_ratio,
+            mapping,
+        }
+    }
+
+    /// Angle to the optical axis for a point at `r` from the center, where 1
+    /// is the edge of the image circle.
+    fn theta(&self, r: f64) -> f64 {
+        match self.mapping {
+            FisheyeMapping::Equidistant => r * self.half_fov,
+            FisheyeMapping::Equisolid => 2.0 * (r * (self.half_fov / 2.0).sin()).asin(),
+        }
+    }
+}
+
+impl Camera for FisheyeCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let x = (2.0 * s - 1.0) * self.aspect_ratio;
+        let y = 2.0 * t - 1.0;
+        let r = (x * x + y * y).sqrt();
+        if r > 1.0 {
+            return None;
+        }
+
+        let theta = self.theta(r);
+        let (sin_phi, cos_phi) = if float_eq_cero!(r) {
+            (0.0, 0.0)
+        } else {
+            (y / r, x / r)
+        };
+        let direction = -self.w * theta.cos() + (self.u * cos_phi + self.v * sin_phi) * theta.sin();
+
+        Some(Ray::new(self.origin, direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(mapping: FisheyeMapping) -> FisheyeCamera {
+        FisheyeCamera::new(
+            Point::ceros(),
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            180.0,
+            1.0,
+            mapping,
+        )
+    }
+
+    #[test]
+    fn image_circle() {
+        for &mapping in [FisheyeMapping::Equidistant, FisheyeMapping::Equisolid].iter() {
+            let camera = camera(mapping);
+
+            let center = camera.get_ray(0.5, 0.5).unwrap();
+            assert!(center
+                .direction()
+                .approx_eq_epsilon(Vec3::new(0.0, 0.0, -1.0), 1e-12));
+
+            // The edge of a 180 degree circle looks sideways
+            let edge = camera.get_ray(1.0, 0.5).unwrap();
+            assert!(edge
+                .direction()
+                .approx_eq_epsilon(Vec3::new(1.0, 0.0, 0.0), 1e-12));
+
+            assert!(camera.get_ray(0.95, 0.95).is_none());
+        }
+
+        // Halfway to the edge the mappings differ
+        let equidistant = camera(FisheyeMapping::Equidistant)
+            .get_ray(0.5, 0.75)
+            .unwrap();
+        assert!(float_eq!(
+            equidistant.direction().y(),
+            (std::f64::consts::PI / 4.0).sin(),
+            1e-12
+        ));
+        let equisolid = camera(FisheyeMapping::Equisolid)
+            .get_ray(0.5, 0.75)
+            .unwrap();
+        assert!(equisolid.direction().y() < equidistant.direction().y());
+    }
+}
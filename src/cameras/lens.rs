@@ -0,0 +1,177 @@
+use std::{f64::consts::PI, path::Path, sync::Arc};
+
+use crate::{image_io, random, Error, Result, Vec3};
+
+/// Shape of the aperture, which is also the shape of out of focus
+/// highlights.
+#[derive(Clone)]
+pub enum ApertureShape {
+    Circle,
+    /// Regular polygon with `blades` sides, rotated `rotation` degrees.
+    Polygon {
+        blades: u32,
+        rotation: f64,
+    },
+    /// Arbitrary shape from an image, see `ApertureMask`.
+    Mask(Arc<ApertureMask>),
+}
+
+impl ApertureShape {
+    /// Uniformly distributed point on the aperture, inside the unit disk
+    /// (or the unit square for masks).
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            ApertureShape::Circle => Vec3::random_in_unit_disk(),
+            ApertureShape::Polygon { blades, rotation } => {
+                let blades = (*blades).max(3) as f64;
+                let sector = (random::random::<f64>() * blades).floor();
+                let angle0 = rotation.to_radians() + sector * 2.0 * PI / blades;
+                let angle1 = angle0 + 2.0 * PI / blades;
+
+                let (mut a, mut b): (f64, f64) = (random::random(), random::random());
+                if a + b > 1.0 {
+                    a = 1.0 - a;
+                    b = 1.0 - b;
+                }
+                Vec3::new(
+                    angle0.cos() * a + angle1.cos() * b,
+                    angle0.sin() * a + angle1.sin() * b,
+                    0.0,
+                )
+            }
+            ApertureShape::Mask(mask) => mask.sample(),
+        }
+    }
+}
+
+/// Grayscale image used as the aperture. Brighter pixels let more light in,
+/// and the image is stretched over the square around the unit disk.
+pub struct ApertureMask {
+    width: usize,
+    height: usize,
+    /// Running sum of the pixel values, to pick pixels proportionally to
+    /// their brightness.
+    cdf: Vec<f64>,
+}
+
+impl ApertureMask {
+    /// `values` are row by row from the top. Fails if they don't match the
+    /// size or are all black.
+    pub fn new(width: usize, height: usize, values: &[f64]) -> Result<Self> {
+        if width.checked_mul(height) != Some(values.len()) {
+            return Err(Error::InvalidConfig(format!(
+                "A {}x{} aperture mask needs {} values, found {}",
+                width,
+                height,
+                width.saturating_mul(height),
+                values.len()
+            )));
+        }
+
+        let mut total = 0.0;
+        let cdf: Vec<f64> = values
+            .iter()
+            .map(|v| {
+                total += v.max(0.0);
+                total
+            })
+            .collect();
+        // Also catches NaN values, which would poison the sum
+        if !(total > 0.0 && total.is_finite()) {
+            return Err(Error::InvalidConfig(
+                "An aperture mask can't be completely black".to_string(),
+            ));
+        }
+
+        Ok(Self { width, height, cdf })
+    }
+
+    pub fn from_png<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let (width, height, values) = image_io::read_grayscale_png(path)?;
+        Self::new(width, height, &values)
+    }
+
+    fn sample(&self) -> Vec3 {
+        let total = self.cdf[self.cdf.len() - 1];
+        let target = random::random::<f64>() * total;
+        let index = self
+            .cdf
+            .partition_point(|&c| c <= target)
+            .min(self.cdf.len() - 1);
+
+        let col = (index % self.width) as f64 + random::random::<f64>();
+        let row = (index / self.width) as f64 + random::random::<f64>();
+        Vec3::new(
+            2.0 * col / self.width as f64 - 1.0,
+            1.0 - 2.0 * row / self.height as f64,
+            0.0,
+        )
+    }
+}
+
+/// Optical effects on top of the thin lens model of `PerspectiveCamera`.
+#[derive(Clone)]
+pub struct Lens {
+    pub aperture_shape: ApertureShape,
+    /// How far the lens barrel clips the aperture towards the corners of
+    /// the image, turning the bokeh into cat eyes. 0 disables it, 1 clips
+    /// the corners of a square image down to nothing.
+    pub cat_eye: f64,
+    /// Lens shift as a fraction of the image width and height.
+    pub shift: (f64, f64),
+    /// Rotation in degrees of the plane of focus around the horizontal and
+    /// vertical axes of the image, as a tilted lens would do. Positive
+    /// angles push it away towards the top and the right of the image.
+    pub tilt: (f64, f64),
+}
+
+impl Default for Lens {
+    fn default() -> Self {
+        Self {
+            aperture_shape: ApertureShape::Circle,
+            cat_eye: 0.0,
+            shift: (0.0, 0.0),
+            tilt: (0.0, 0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polygon_inside_its_edges() {
+        let shape = ApertureShape::Polygon {
+            blades: 6,
+            rotation: 0.0,
+        };
+        // Apothem of a unit hexagon
+        let apothem = (PI / 6.0).cos();
+
+        for _ in 0..1000 {
+            let p = shape.sample();
+            let angle = p.y().atan2(p.x()).rem_euclid(PI / 3.0) - PI / 6.0;
+            assert!(p.len() * angle.cos() <= apothem + 1e-12);
+        }
+    }
+
+    #[test]
+    fn mask_only_where_lit() {
+        // Only the top right pixel lets light through
+        let mask = ApertureMask::new(2, 2, &[0.0, 1.0, 0.0, 0.0]).unwrap();
+
+        for _ in 0..100 {
+            let p = mask.sample();
+            assert!(p.x() >= 0.0 && p.x() <= 1.0);
+            assert!(p.y() >= 0.0 && p.y() <= 1.0);
+        }
+    }
+
+    #[test]
+    fn bad_masks() {
+        assert!(ApertureMask::new(2, 2, &[1.0; 3]).is_err());
+        assert!(ApertureMask::new(2, 2, &[0.0; 4]).is_err());
+        assert!(ApertureMask::new(usize::MAX, 2, &[1.0]).is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{Point, Ray, Vec3};
+
+mod cube_map;
+mod equirectangular;
+mod fisheye;
+mod lens;
+mod omni_stereo;
+mod orthographic;
+mod perspective;
+mod physical;
+mod realistic;
+mod stereo;
+
+pub use cube_map::CubeMapCamera;
+pub use equirectangular::EquirectangularCamera;
+pub use fisheye::{FisheyeCamera, FisheyeMapping};
+pub use lens::{ApertureMask, ApertureShape, Lens};
+pub use omni_stereo::OmniStereoCamera;
+pub use orthographic::OrthographicCamera;
+pub use perspective::PerspectiveCamera;
+pub use physical::PhysicalCamera;
+pub use realistic::{LensElement, RealisticCamera};
+pub use stereo::{StereoCamera, StereoLayout};
+
+/// Maps a point of the image to a ray leaving the camera. `s` goes from the
+/// left edge (0) to the right one (1) and `t` from the bottom (0) to the top
+/// (1). Points that the projection doesn't cover return `None` and are left
+/// black.
+pub trait Camera: Send + Sync {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray>;
+}
+
+/// Projection used to build the camera for a render, see
+/// `CameraConfig::camera`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Projection {
+    /// Thin lens, with the effects in `CameraConfig::lens`.
+    Perspective,
+    /// Parallel rays, `height` is the height of the view in world units.
+    Orthographic { height: f64 },
+    /// Circular image covering `fov` degrees across its diameter, which
+    /// matches the image height.
+    Fisheye { fov: f64, mapping: FisheyeMapping },
+    /// Full 360 by 180 degrees panorama, meant for 2:1 images.
+    Equirectangular,
+    /// Six 90 degree faces in a 3x2 grid, meant for 3:2 images.
+    CubeMap,
+    /// Perspective views for both eyes in one image. The image aspect ratio
+    /// covers both views, so a side by side pair of 16:9 eyes is 32:9.
+    Stereo {
+        ipd: f64,
+        convergence: f64,
+        layout: StereoLayout,
+    },
+    /// Omni-directional stereo panorama in top-bottom layout, meant for 1:1
+    /// images.
+    OmniStereo { ipd: f64 },
+    /// Traced through a lens description, see `RealisticCamera`. The film
+    /// diagonal is in lens units, and `scale` converts those into world
+    /// units.
+    Realistic {
+        elements: Vec<LensElement>,
+        film_diagonal: f64,
+        scale: f64,
+    },
+}
+
+/// Right (`u`), up (`v`) and backwards (`w`) vectors of a camera at
+/// `lookfrom` looking at `lookat`.
+fn view_frame(lookfrom: Point, lookat: Point, vup: Vec3) -> (Vec3, Vec3, Vec3) {
+    let w = (lookfrom - lookat).unit_vector();
+    let u = vup.cross(w).unit_vector();
+    let v = w.cross(u);
+
+    (u, v, w)
+}
@@ -0,0 +1,87 @@
+use std::f64::consts::PI;
+
+use super::{view_frame, Camera};
+use crate::{Point, Ray, Vec3};
+
+/// Omni-directional stereo (ODS) panorama, left eye on top of the right one.
+///
+/// Each eye is an equirectangular panorama whose rays start on a circle of
+/// `ipd` diameter around `lookfrom`, tangent to it, so every viewing
+/// direction gets the right horizontal parallax when seen in a headset.
+pub struct OmniStereoCamera {
+    origin: Point,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    half_ipd: f64,
+}
+
+impl OmniStereoCamera {
+    pub fn new(lookfrom: Point, lookat: Point, vup: Vec3, ipd: f64) -> Self {
+        let (u, v, w) = view_frame(lookfrom, lookat, vup);
+
+        Self {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            half_ipd: ipd / 2.0,
+        }
+    }
+}
+
+impl Camera for OmniStereoCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let (side, t) = if t >= 0.5 {
+            (-1.0, t * 2.0 - 1.0)
+        } else {
+            (1.0, t * 2.0)
+        };
+
+        let longitude = (s - 0.5) * 2.0 * PI;
+        let latitude = (t - 0.5) * PI;
+        let (sin_lon, cos_lon) = longitude.sin_cos();
+        let (sin_lat, cos_lat) = latitude.sin_cos();
+
+        let forward = -self.w * cos_lon + self.u * sin_lon;
+        let right = self.u * cos_lon + self.w * sin_lon;
+        let direction = forward * cos_lat + self.v * sin_lat;
+
+        Some(Ray::new(
+            self.origin + right * (side * self.half_ipd),
+            direction,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eyes_on_the_circle() {
+        let camera = OmniStereoCamera::new(
+            Point::ceros(),
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            0.064,
+        );
+
+        for &s in [0.1, 0.5, 0.8].iter() {
+            let left = camera.get_ray(s, 0.75).unwrap();
+            let right = camera.get_ray(s, 0.25).unwrap();
+
+            assert!(left.direction().approx_eq_epsilon(right.direction(), 1e-12));
+            assert!(float_eq!(left.origin().len(), 0.032, 1e-12));
+            assert!(float_eq!(left.origin().dot(left.direction()), 0.0, 1e-12));
+            assert!((right.origin() + left.origin()).approx_eq_epsilon(Vec3::ceros(), 1e-12));
+        }
+
+        // Looking forward the left eye is on the left
+        let left = camera.get_ray(0.5, 0.75).unwrap();
+        assert!(left
+            .direction()
+            .approx_eq_epsilon(Vec3::new(0.0, 0.0, -1.0), 1e-12));
+        assert!(float_eq!(left.origin().x(), -0.032, 1e-12));
+    }
+}
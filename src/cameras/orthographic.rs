@@ -0,0 +1,37 @@
+use super::{view_frame, Camera};
+use crate::{Point, Ray, Vec3};
+
+/// Parallel projection, for technical drawings where sizes must not depend
+/// on the distance to the camera.
+pub struct OrthographicCamera {
+    lower_left: Point,
+    horizontal: Vec3,
+    vertical: Vec3,
+    direction: Vec3,
+}
+
+impl OrthographicCamera {
+    pub fn new(lookfrom: Point, lookat: Point, vup: Vec3, height: f64, aspect_ratio: f64) -> Self {
+        let (u, v, w) = view_frame(lookfrom, lookat, vup);
+
+        let horizontal = u * height * aspect_ratio;
+        let vertical = v * height;
+        let lower_left = lookfrom - horizontal / 2.0 - vertical / 2.0;
+
+        Self {
+            lower_left,
+            horizontal,
+            vertical,
+            direction: -w,
+        }
+    }
+}
+
+impl Camera for OrthographicCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        Some(Ray::new(
+            self.lower_left + self.horizontal * s + self.vertical * t,
+            self.direction,
+        ))
+    }
+}
@@ -0,0 +1,181 @@
+use super::{Camera, Lens};
+use crate::{Point, Ray, Vec3};
+
+/// Thin lens camera. The aperture is circular unless a `Lens` with another
+/// shape is given through `with_lens`.
+pub struct PerspectiveCamera {
+    origin: Point,
+    lower_left: Point,
+    horizontal: Vec3,
+    vertical: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    lens_radius: f64,
+    focus_dist: f64,
+    /// Normal of the plane of focus, `w` unless the lens is tilted.
+    focus_normal: Vec3,
+    lens: Lens,
+}
+
+impl PerspectiveCamera {
+    pub fn new(
+        lookfrom: Point,
+        lookat: Point,
+        vup: Vec3,
+        vert_fov: f64,
+        aspect_ratio: f64,
+        aperture: f64,
+        focus_dist: f64,
+    ) -> Self {
+        let theta = vert_fov.to_radians();
+        let h = (theta / 2.0).tan();
+
+        let viewport_height = 2.0 * h;
+        let viewport_width = aspect_ratio * viewport_height;
+
+        let w = (lookfrom - lookat).unit_vector();
+        let u = vup.cross(w).unit_vector();
+        let v = w.cross(u);
+
+        let origin = lookfrom;
+        let horizontal = u * viewport_width * focus_dist;
+        let vertical = v * viewport_height * focus_dist;
+        let lower_left = origin - horizontal / 2.0 - vertical / 2.0 - w * focus_dist;
+
+        let lens_radius = aperture / 2.0;
+        Self {
+            origin,
+            lower_left,
+            horizontal,
+            vertical,
+            u,
+            v,
+            w,
+            lens_radius,
+            focus_dist,
+            focus_normal: w,
+            lens: Lens::default(),
+        }
+    }
+
+    pub fn with_lens(mut self, lens: Lens) -> Self {
+        self.lower_left =
+            self.lower_left + self.horizontal * lens.shift.0 + self.vertical * lens.shift.1;
+
+        let (tilt_x, tilt_y) = lens.tilt;
+        self.focus_normal =
+            (self.w + self.v * tilt_x.to_radians().tan() + self.u * tilt_y.to_radians().tan())
+                .unit_vector();
+
+        self.lens = lens;
+        self
+    }
+
+    /// Point in focus seen through the center of the lens at `(s, t)`.
+    fn focus_point(&self, s: f64, t: f64) -> Point {
+        let window = self.lower_left + self.horizontal * s + self.vertical * t;
+        let direction = window - self.origin;
+
+        // The window already is the plane of focus unless tilted
+        let denom = self.focus_normal.dot(direction);
+        if float_eq_cero!(denom, 1e-12) {
+            return window;
+        }
+        let t_focus = -self.focus_dist * self.focus_normal.dot(self.w) / denom;
+        if t_focus <= 0.0 {
+            return window;
+        }
+
+        self.origin + direction * t_focus
+    }
+}
+
+impl Camera for PerspectiveCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let sample = self.lens.aperture_shape.sample();
+
+        if self.lens.cat_eye > 0.0 {
+            // Barrel opening as seen from this point of the image
+            let center = Vec3::new(2.0 * s - 1.0, 2.0 * t - 1.0, 0.0) * self.lens.cat_eye;
+            if (sample - center).len2() > 1.0 {
+                return None;
+            }
+        }
+
+        let rd = sample * self.lens_radius;
+        let offset = self.u * rd.x() + self.v * rd.y();
+
+        Some(Ray::new(
+            self.origin + offset,
+            self.focus_point(s, t) - self.origin - offset,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera() -> PerspectiveCamera {
+        PerspectiveCamera::new(
+            Point::ceros(),
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            1.0,
+            0.5,
+            2.0,
+        )
+    }
+
+    #[test]
+    fn in_focus_regardless_of_lens_sample() {
+        let camera = camera();
+        for _ in 0..10 {
+            let ray = camera.get_ray(0.75, 0.5).unwrap();
+            let t = (-2.0 - ray.origin().z()) / ray.direction().z();
+            assert!(ray
+                .at(t)
+                .approx_eq_epsilon(Point::new(1.0, 0.0, -2.0), 1e-12));
+        }
+    }
+
+    #[test]
+    fn shift_and_tilt() {
+        let shifted = camera().with_lens(Lens {
+            shift: (0.25, 0.0),
+            ..Lens::default()
+        });
+        assert!(shifted
+            .focus_point(0.5, 0.5)
+            .approx_eq_epsilon(Point::new(1.0, 0.0, -2.0), 1e-12));
+
+        // Tilted 45 degrees the plane of focus goes away towards the top
+        let tilted = camera().with_lens(Lens {
+            tilt: (45.0, 0.0),
+            ..Lens::default()
+        });
+        assert!(tilted
+            .focus_point(0.5, 0.5)
+            .approx_eq_epsilon(Point::new(0.0, 0.0, -2.0), 1e-12));
+        assert!(tilted
+            .focus_point(0.5, 0.75)
+            .approx_eq_epsilon(Point::new(0.0, 2.0, -4.0), 1e-12));
+        assert!(tilted.focus_point(0.5, 0.25).z() > -2.0);
+    }
+
+    #[test]
+    fn cat_eye_vignetting() {
+        let camera = camera().with_lens(Lens {
+            cat_eye: 1.0,
+            ..Lens::default()
+        });
+
+        let blocked = (0..1000)
+            .filter(|_| camera.get_ray(1.0, 1.0).is_none())
+            .count();
+        assert!(blocked > 500);
+        assert!((0..100).all(|_| camera.get_ray(0.5, 0.5).is_some()));
+    }
+}
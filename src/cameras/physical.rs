@@ -0,0 +1,95 @@
+/// Camera body and lens settings, used by `CameraConfig` instead of the
+/// field of view and aperture. Lengths are in millimeters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhysicalCamera {
+    pub f_number: f64,
+    /// Exposure time in seconds.
+    pub shutter_speed: f64,
+    pub iso: f64,
+    pub focal_length: f64,
+    /// Width and height of the sensor.
+    pub sensor: (f64, f64),
+    /// World units in a millimeter, to turn the lens opening into an
+    /// aperture. The default takes world units as meters.
+    pub units_per_mm: f64,
+}
+
+impl Default for PhysicalCamera {
+    /// 50mm lens at f/8, 1/125 s and ISO 100 on a full frame sensor.
+    fn default() -> Self {
+        Self {
+            f_number: 8.0,
+            shutter_speed: 1.0 / 125.0,
+            iso: 100.0,
+            focal_length: 50.0,
+            sensor: (36.0, 24.0),
+            units_per_mm: 0.001,
+        }
+    }
+}
+
+impl PhysicalCamera {
+    /// Vertical field of view in degrees. The image is fit inside the
+    /// sensor, so an image wider than the sensor uses all of its width and
+    /// a narrower one all of its height.
+    pub fn vert_fov(&self, aspect_ratio: f64) -> f64 {
+        let (width, height) = self.sensor;
+        let height = height.min(width / aspect_ratio);
+        2.0 * (height / (2.0 * self.focal_length)).atan().to_degrees()
+    }
+
+    /// Diameter of the lens opening in world units.
+    pub fn aperture(&self) -> f64 {
+        self.focal_length / self.f_number * self.units_per_mm
+    }
+
+    /// Scale applied to the rendered colors, relative to the default
+    /// settings. It grows with the exposure time and the ISO, and shrinks
+    /// with the square of the f-number.
+    pub fn exposure(&self) -> f64 {
+        let reference = Self::default();
+        let light = |c: &Self| c.shutter_speed * c.iso / (c.f_number * c.f_number);
+        light(self) / light(&reference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn field_of_view_fits_sensor() {
+        let camera = PhysicalCamera::default();
+        let fov = 2.0 * (12.0f64 / 50.0).atan().to_degrees();
+
+        assert!(float_eq!(camera.vert_fov(1.5), fov));
+        assert!(float_eq!(camera.vert_fov(1.0), fov));
+        // Wider than the sensor, the height is cropped
+        let cropped = 2.0 * (6.0f64 / 50.0).atan().to_degrees();
+        assert!(float_eq!(camera.vert_fov(3.0), cropped));
+    }
+
+    #[test]
+    fn exposure_stops() {
+        let camera = PhysicalCamera::default();
+        assert!(float_eq!(camera.exposure(), 1.0));
+        assert!(float_eq!(camera.aperture(), 0.00625, 1e-12));
+
+        // Each of these is one stop brighter
+        let slower = PhysicalCamera {
+            shutter_speed: 2.0 / 125.0,
+            ..camera
+        };
+        let faster_film = PhysicalCamera {
+            iso: 200.0,
+            ..camera
+        };
+        let wider = PhysicalCamera {
+            f_number: 8.0 / 2f64.sqrt(),
+            ..camera
+        };
+        for c in [slower, faster_film, wider].iter() {
+            assert!(float_eq!(c.exposure(), 2.0, 1e-12));
+        }
+    }
+}
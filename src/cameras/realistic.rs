@@ -0,0 +1,334 @@
+use super::{view_frame, Camera};
+use crate::{Error, Point, Ray, Result, Vec3};
+
+/// One refracting surface (or the aperture stop) of a lens description, in
+/// the usual prescription table format. Elements are listed from the front
+/// of the lens to the film.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LensElement {
+    /// Radius of curvature, positive when its center is on the film side. 0
+    /// makes a flat surface, as used for the aperture stop.
+    pub radius: f64,
+    /// Distance along the axis to the next surface. The last one is ignored,
+    /// the film is placed where the lens focuses at `focus_dist`.
+    pub thickness: f64,
+    /// Index of refraction between this surface and the next one, 0 or 1
+    /// for air.
+    pub ior: f64,
+    /// Diameter of the surface.
+    pub aperture: f64,
+}
+
+impl LensElement {
+    pub fn new(radius: f64, thickness: f64, ior: f64, aperture: f64) -> Self {
+        Self {
+            radius,
+            thickness,
+            ior,
+            aperture,
+        }
+    }
+
+    /// 50mm f/2 double Gauss, in millimeters.
+    pub fn double_gauss_50mm() -> Vec<LensElement> {
+        vec![
+            LensElement::new(29.475, 3.76, 1.67, 25.2),
+            LensElement::new(84.83, 0.12, 1.0, 25.2),
+            LensElement::new(19.275, 4.025, 1.67, 23.0),
+            LensElement::new(40.77, 3.275, 1.699, 23.0),
+            LensElement::new(12.75, 5.705, 1.0, 18.0),
+            LensElement::new(0.0, 4.5, 0.0, 17.1),
+            LensElement::new(-14.495, 1.18, 1.603, 17.0),
+            LensElement::new(40.77, 6.065, 1.658, 20.0),
+            LensElement::new(-20.385, 0.19, 1.0, 20.0),
+            LensElement::new(437.065, 3.22, 1.717, 20.0),
+            LensElement::new(-39.73, 0.0, 1.0, 20.0),
+        ]
+    }
+
+    fn medium(&self) -> f64 {
+        if self.ior == 0.0 {
+            1.0
+        } else {
+            self.ior
+        }
+    }
+}
+
+/// Camera that traces rays from the film through every surface of a lens
+/// description, which gives the distortion, vignetting and bokeh of that
+/// lens. Rays blocked inside the lens are tried again a few times before
+/// the sample is given up as black.
+///
+/// The lens is in its own units (usually millimeters) and `scale` converts
+/// them to world units.
+pub struct RealisticCamera {
+    origin: Point,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    elements: Vec<LensElement>,
+    /// Position of each surface along the axis. The film is at 0 and the
+    /// scene towards negative values.
+    positions: Vec<f64>,
+    film_width: f64,
+    film_height: f64,
+    scale: f64,
+}
+
+const MAX_LENS_ATTEMPTS: u32 = 16;
+
+impl RealisticCamera {
+    /// Fails without lens elements.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        lookfrom: Point,
+        lookat: Point,
+        vup: Vec3,
+        elements: Vec<LensElement>,
+        film_diagonal: f64,
+        aspect_ratio: f64,
+        focus_dist: f64,
+        scale: f64,
+    ) -> Result<Self> {
+        if elements.is_empty() {
+            return Err(Error::InvalidConfig(
+                "A lens needs at least one element".to_string(),
+            ));
+        }
+        let (u, v, w) = view_frame(lookfrom, lookat, vup);
+
+        let film_height = film_diagonal / (1.0 + aspect_ratio * aspect_ratio).sqrt();
+        let mut camera = Self {
+            origin: lookfrom,
+            u,
+            v,
+            w,
+            elements,
+            positions: Vec::new(),
+            film_width: film_height * aspect_ratio,
+            film_height,
+            scale,
+        };
+        camera.place_elements(0.0);
+        let film = camera.image_distance(focus_dist / scale);
+        camera.place_elements(film);
+        Ok(camera)
+    }
+
+    /// Puts the last surface `back_focus` in front of the film.
+    fn place_elements(&mut self, back_focus: f64) {
+        let mut z = -back_focus;
+        let mut positions = vec![0.0; self.elements.len()];
+        for i in (0..self.elements.len()).rev() {
+            positions[i] = z;
+            if i > 0 {
+                z -= self.elements[i - 1].thickness;
+            }
+        }
+        self.positions = positions;
+    }
+
+    /// Distance behind the last surface where an on-axis object
+    /// `distance` units in front of the first surface comes to focus.
+    fn image_distance(&self, distance: f64) -> f64 {
+        let height = self.elements[0].aperture * 0.01;
+        let object = Point::new(0.0, 0.0, self.positions[0] - distance);
+        let mut o = object;
+        let mut d = Point::new(height, 0.0, self.positions[0]) - object;
+        let mut ior = 1.0;
+
+        for (i, element) in self.elements.iter().enumerate() {
+            let (p, n) = match self.intersect(i, o, d) {
+                Some(hit) => hit,
+                None => continue,
+            };
+            let next = element.medium();
+            d = match refract(d.unit_vector(), n, ior / next) {
+                Some(d) => d,
+                None => return 0.0,
+            };
+            o = p;
+            ior = next;
+        }
+
+        // Where the ray crosses the axis again
+        let last = self.positions[self.positions.len() - 1];
+        let t = -o.x() / d.x();
+        (o.z() + d.z() * t) - last
+    }
+
+    /// Hit point and normal facing the incoming ray on surface `i`.
+    fn intersect(&self, i: usize, o: Point, d: Vec3) -> Option<(Point, Vec3)> {
+        let element = &self.elements[i];
+        let z = self.positions[i];
+
+        let (p, normal) = if element.radius == 0.0 {
+            if float_eq_cero!(d.z()) {
+                return None;
+            }
+            let t = (z - o.z()) / d.z();
+            if t <= 0.0 {
+                return None;
+            }
+            (o + d * t, Vec3::new(0.0, 0.0, 1.0))
+        } else {
+            let center = Point::new(0.0, 0.0, z + element.radius);
+            let oc = o - center;
+            let a = d.len2();
+            let half_b = oc.dot(d);
+            let c = oc.len2() - element.radius * element.radius;
+            let disc = half_b * half_b - a * c;
+            if disc < 0.0 {
+                return None;
+            }
+            let sqrt = disc.sqrt();
+            // Of the two crossings of the sphere, the lens surface is the
+            // one next to the vertex
+            let p = [(-half_b - sqrt) / a, (-half_b + sqrt) / a]
+                .iter()
+                .filter(|&&t| t > 1e-9)
+                .map(|&t| o + d * t)
+                .min_by(|p0, p1| (p0.z() - z).abs().partial_cmp(&(p1.z() - z).abs()).unwrap())?;
+            (p, (p - center).unit_vector())
+        };
+
+        let r2 = p.x() * p.x() + p.y() * p.y();
+        if r2 > element.aperture * element.aperture / 4.0 {
+            return None;
+        }
+
+        let normal = if normal.dot(d) > 0.0 { -normal } else { normal };
+        Some((p, normal))
+    }
+
+    /// Traces a ray leaving the film at `film` towards `rear` on the last
+    /// surface, returning it once it leaves the front of the lens.
+    fn trace_from_film(&self, film: Point, rear: Point) -> Option<(Point, Vec3)> {
+        let mut o = film;
+        let mut d = (rear - film).unit_vector();
+
+        for i in (0..self.elements.len()).rev() {
+            let (p, n) = self.intersect(i, o, d)?;
+            if self.elements[i].radius != 0.0 {
+                let outside = if i > 0 {
+                    self.elements[i - 1].medium()
+                } else {
+                    1.0
+                };
+                d = refract(d, n, self.elements[i].medium() / outside)?;
+            }
+            o = p;
+        }
+
+        Some((o, d))
+    }
+}
+
+/// Refracts the unit vector `d` through a surface with normal `n` facing it,
+/// or `None` on total internal reflection.
+fn refract(d: Vec3, n: Vec3, eta: f64) -> Option<Vec3> {
+    let cos_i = -n.dot(d);
+    let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+    if sin2_t > 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+
+    Some((d * eta + n * (eta * cos_i - cos_t)).unit_vector())
+}
+
+impl Camera for RealisticCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        // The image on the film is upside down
+        let film = Point::new(
+            (0.5 - s) * self.film_width,
+            (0.5 - t) * self.film_height,
+            0.0,
+        );
+        let last = self.elements.len() - 1;
+        let rear_radius = self.elements[last].aperture / 2.0;
+
+        for _ in 0..MAX_LENS_ATTEMPTS {
+            let pupil = Vec3::random_in_unit_disk() * rear_radius;
+            let rear = Point::new(pupil.x(), pupil.y(), self.positions[last]);
+            if let Some((o, d)) = self.trace_from_film(film, rear) {
+                let to_world = |a: Vec3| self.u * a.x() + self.v * a.y() + self.w * a.z();
+                return Some(Ray::new(
+                    self.origin + to_world(o) * self.scale,
+                    to_world(d),
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(focus_dist: f64) -> RealisticCamera {
+        RealisticCamera::new(
+            Point::ceros(),
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            LensElement::double_gauss_50mm(),
+            43.27,
+            1.5,
+            focus_dist,
+            0.001,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn focuses_on_axis() {
+        let focus_dist = 2.0;
+        let camera = camera(focus_dist);
+        let last = camera.elements.len() - 1;
+        let front = camera.positions[0] * camera.scale;
+
+        for &(x, y) in [(0.0, 2.0), (3.0, -1.0), (-4.0, 0.5)].iter() {
+            let rear = Point::new(x, y, camera.positions[last]);
+            let (o, d) = camera.trace_from_film(Point::ceros(), rear).unwrap();
+            let o = o * camera.scale;
+
+            // Every ray from the center of the film meets the axis at the
+            // focus distance
+            let t = (front - focus_dist - o.z()) / d.z();
+            let p = o + d * t;
+            assert!(p.x().abs() < 5e-4 && p.y().abs() < 5e-4, "{}", p);
+        }
+    }
+
+    #[test]
+    fn inverted_image() {
+        let camera = camera(10.0);
+
+        // Off center points of the film look towards the opposite side
+        let ray = (0..100)
+            .filter_map(|_| camera.get_ray(0.9, 0.5))
+            .next()
+            .unwrap();
+        assert!(ray.direction().x() > 0.0);
+        assert!(ray.direction().z() < 0.0);
+        assert!(float_eq!(ray.direction().y(), 0.0, 0.05));
+    }
+
+    #[test]
+    fn needs_elements() {
+        let camera = RealisticCamera::new(
+            Point::ceros(),
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec::new(),
+            43.27,
+            1.5,
+            2.0,
+            0.001,
+        );
+        assert!(camera.is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use super::{Camera, Lens, PerspectiveCamera};
+use crate::{Point, Ray, Vec3};
+
+/// Where each eye goes in the combined image. The left eye is always first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StereoLayout {
+    SideBySide,
+    TopBottom,
+}
+
+/// Pair of perspective cameras `ipd` apart, rendered into one image.
+///
+/// The eyes keep parallel axes and shift their frustums instead of toeing
+/// in, so objects at `convergence` distance land on the same spot of both
+/// views without the vertical parallax toe-in introduces.
+pub struct StereoCamera {
+    eyes: [PerspectiveCamera; 2],
+    layout: StereoLayout,
+}
+
+impl StereoCamera {
+    /// `vert_fov` and `aspect_ratio` are the ones of each eye.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        lookfrom: Point,
+        lookat: Point,
+        vup: Vec3,
+        vert_fov: f64,
+        aspect_ratio: f64,
+        aperture: f64,
+        focus_dist: f64,
+        ipd: f64,
+        convergence: f64,
+        layout: StereoLayout,
+    ) -> Self {
+        let viewport_width = aspect_ratio * 2.0 * (vert_fov.to_radians() / 2.0).tan();
+        let right = vup.cross(lookfrom - lookat).unit_vector();
+
+        let eye = |side: f64| {
+            let offset = right * (side * ipd / 2.0);
+            // Towards the middle, by the share of the view the eye is off
+            // at the convergence distance
+            let shift = -side * ipd / 2.0 / (viewport_width * convergence);
+            PerspectiveCamera::new(
+                lookfrom + offset,
+                lookat + offset,
+                vup,
+                vert_fov,
+                aspect_ratio,
+                aperture,
+                focus_dist,
+            )
+            .with_lens(Lens {
+                shift: (shift, 0.0),
+                ..Lens::default()
+            })
+        };
+
+        Self {
+            eyes: [eye(-1.0), eye(1.0)],
+            layout,
+        }
+    }
+
+    /// Gives both eyes `lens`. Its shift adds to the one that makes them
+    /// converge.
+    pub fn with_lens(self, lens: Lens) -> Self {
+        let [left, right] = self.eyes;
+        Self {
+            eyes: [left.with_lens(lens.clone()), right.with_lens(lens)],
+            layout: self.layout,
+        }
+    }
+}
+
+impl Camera for StereoCamera {
+    fn get_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        let (eye, s, t) = match self.layout {
+            StereoLayout::SideBySide if s < 0.5 => (0, s * 2.0, t),
+            StereoLayout::SideBySide => (1, s * 2.0 - 1.0, t),
+            StereoLayout::TopBottom if t >= 0.5 => (0, s, t * 2.0 - 1.0),
+            StereoLayout::TopBottom => (1, s, t * 2.0),
+        };
+
+        self.eyes[eye].get_ray(s, t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(layout: StereoLayout) -> StereoCamera {
+        StereoCamera::new(
+            Point::ceros(),
+            Point::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            60.0,
+            1.0,
+            0.0,
+            4.0,
+            0.064,
+            2.0,
+            layout,
+        )
+    }
+
+    #[test]
+    fn zero_parallax_at_convergence() {
+        let camera = camera(StereoLayout::SideBySide);
+
+        // The same spot of each view meets on the convergence window, which
+        // is halfway to the focus plane here
+        for &(s, t) in [(0.25, 0.5), (0.1, 0.8)].iter() {
+            let left = camera.get_ray(s, t).unwrap();
+            let right = camera.get_ray(s + 0.5, t).unwrap();
+
+            assert!(float_eq!(left.origin().x(), -0.032, 1e-12));
+            assert!(float_eq!(right.origin().x(), 0.032, 1e-12));
+            assert!(float_eq!(left.at(0.5).z(), -2.0, 1e-12));
+            assert!(left.at(0.5).approx_eq_epsilon(right.at(0.5), 1e-12));
+        }
+
+        // With parallel axes there is no vertical parallax anywhere
+        let left = camera.get_ray(0.05, 0.9).unwrap();
+        let right = camera.get_ray(0.55, 0.9).unwrap();
+        assert!(float_eq!(left.at(3.0).y(), right.at(3.0).y(), 1e-12));
+    }
+
+    #[test]
+    fn top_bottom_layout() {
+        let camera = camera(StereoLayout::TopBottom);
+
+        assert!(float_eq!(
+            camera.get_ray(0.5, 0.75).unwrap().origin().x(),
+            -0.032,
+            1e-12
+        ));
+        assert!(float_eq!(
+            camera.get_ray(0.5, 0.25).unwrap().origin().x(),
+            0.032,
+            1e-12
+        ));
+    }
+
+    #[test]
+    fn lens_shift_moves_both_eyes() {
+        let shifted = camera(StereoLayout::SideBySide).with_lens(Lens {
+            shift: (0.25, 0.0),
+            ..Lens::default()
+        });
+        let plain = camera(StereoLayout::SideBySide);
+        let width = 2.0 * (30.0f64).to_radians().tan() * 4.0;
+
+        for &s in [0.25, 0.75].iter() {
+            let moved = shifted.get_ray(s, 0.5).unwrap().at(1.0);
+            let still = plain.get_ray(s, 0.5).unwrap().at(1.0);
+            assert!(float_eq!(moved.x() - still.x(), 0.25 * width, 1e-9));
+            assert!(float_eq!(moved.z(), -4.0, 1e-12));
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use std::str::FromStr;
+
+use rayon::prelude::*;
+
+use crate::{Color, Error, Image, Result};
+
+/// Metric of a `Comparison`, to check against a threshold.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metric {
+    Mse,
+    Rmse,
+    RelMse,
+    Psnr,
+    Ssim,
+    Flip,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 6] = [
+        Metric::Mse,
+        Metric::Rmse,
+        Metric::RelMse,
+        Metric::Psnr,
+        Metric::Ssim,
+        Metric::Flip,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Mse => "mse",
+            Metric::Rmse => "rmse",
+            Metric::RelMse => "relmse",
+            Metric::Psnr => "psnr",
+            Metric::Ssim => "ssim",
+            Metric::Flip => "flip",
+        }
+    }
+
+    /// Whether larger values mean closer images.
+    fn is_similarity(&self) -> bool {
+        matches!(self, Metric::Psnr | Metric::Ssim)
+    }
+}
+
+impl FromStr for Metric {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Metric::ALL
+            .iter()
+            .find(|metric| metric.name() == s.to_lowercase())
+            .copied()
+            .ok_or_else(|| Error::Parse(format!("Unknown metric {}", s)))
+    }
+}
+
+/// How far an image is from a reference one, see `compare`.
+pub struct Comparison {
+    /// Mean squared error of the linear color channels.
+    pub mse: f64,
+    pub rmse: f64,
+    /// Squared errors relative to the squared reference, which keeps dark
+    /// areas from counting less than bright ones.
+    pub rel_mse: f64,
+    /// Peak signal to noise ratio in decibels, for a peak of 1. Infinite
+    /// for the same image.
+    pub psnr: f64,
+    /// Mean structural similarity of the displayed luminance, 1 being the
+    /// same image.
+    pub ssim: f64,
+    /// Mean perceived error of the displayed colors between 0 and 1, a
+    /// simplified take on NVIDIA's FLIP.
+    pub flip: f64,
+    /// The FLIP error of each pixel in false color, from black for none
+    /// through purple and orange to light yellow for the most.
+    pub difference: Image,
+}
+
+impl Comparison {
+    pub fn value(&self, metric: Metric) -> f64 {
+        match metric {
+            Metric::Mse => self.mse,
+            Metric::Rmse => self.rmse,
+            Metric::RelMse => self.rel_mse,
+            Metric::Psnr => self.psnr,
+            Metric::Ssim => self.ssim,
+            Metric::Flip => self.flip,
+        }
+    }
+
+    /// Whether the image is within `threshold` of the reference: at most
+    /// that error, or at least that PSNR or SSIM.
+    pub fn passes(&self, metric: Metric, threshold: f64) -> bool {
+        if metric.is_similarity() {
+            self.value(metric) >= threshold
+        } else {
+            self.value(metric) <= threshold
+        }
+    }
+}
+
+/// Compares `image` with `reference`, both in linear color, of the same
+/// size and not empty. SSIM and FLIP look at them gamma corrected and clamped as they
+/// would be displayed.
+pub fn compare(reference: &Image, image: &Image) -> Result<Comparison> {
+    if (reference.width(), reference.height()) != (image.width(), image.height()) {
+        return Err(Error::InvalidConfig(format!(
+            "Can't compare a {}x{} image with a {}x{} one",
+            image.width(),
+            image.height(),
+            reference.width(),
+            reference.height()
+        )));
+    }
+    if image.pixels().is_empty() {
+        return Err(Error::InvalidConfig(format!(
+            "Can't compare an empty {}x{} image",
+            image.width(),
+            image.height()
+        )));
+    }
+
+    let values = (reference.pixels().len() * 3) as f64;
+    let (mut squared, mut relative) = (0.0, 0.0);
+    for (&r, &c) in reference.pixels().iter().zip(image.pixels()) {
+        let error = (c - r) * (c - r);
+        squared += error[0] + error[1] + error[2];
+        let relative_error = error / (r * r + Color::ones() * 0.01);
+        relative += relative_error[0] + relative_error[1] + relative_error[2];
+    }
+    let mse = squared / values;
+
+    let flip = flip(reference, image);
+    Ok(Comparison {
+        mse,
+        rmse: mse.sqrt(),
+        rel_mse: relative / values,
+        psnr: -10.0 * mse.log10(),
+        ssim: mean(&ssim(reference, image)),
+        flip: mean(&flip),
+        difference: Image::from_pixels(
+            reference.width(),
+            reference.height(),
+            flip.iter().map(|&e| false_color(e)).collect(),
+        ),
+    })
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// Color as displayed, gamma corrected and clamped like `Image::to_rgb8`.
+fn display(c: Color) -> Color {
+    let channel = |v: f64| v.max(0.0).sqrt().min(1.0);
+    Color::new(channel(c[0]), channel(c[1]), channel(c[2]))
+}
+
+/// One value per pixel of `image`.
+fn plane(image: &Image, value: impl Fn(Color) -> f64) -> Vec<f64> {
+    image.pixels().iter().map(|&c| value(c)).collect()
+}
+
+/// Gaussian blur of a plane of `width` values per row, weighting only the
+/// values inside it.
+fn blur(values: &[f64], width: usize, sigma: f64) -> Vec<f64> {
+    let height = values.len() / width;
+    let reach = (3.0 * sigma).ceil() as i64;
+    let weights: Vec<f64> = (-reach..=reach)
+        .map(|d| (-(d * d) as f64 / (2.0 * sigma * sigma)).exp())
+        .collect();
+
+    let pass = |values: &[f64], horizontal: bool| -> Vec<f64> {
+        (0..values.len())
+            .into_par_iter()
+            .map(|i| {
+                let (x, y) = ((i % width) as i64, (i / width) as i64);
+                let (mut sum, mut total) = (0.0, 0.0);
+                for (w, d) in weights.iter().zip(-reach..=reach) {
+                    let (qx, qy) = if horizontal { (x + d, y) } else { (x, y + d) };
+                    if qx >= 0 && qy >= 0 && qx < width as i64 && qy < height as i64 {
+                        sum += w * values[qy as usize * width + qx as usize];
+                        total += w;
+                    }
+                }
+                sum / total
+            })
+            .collect()
+    };
+    pass(&pass(values, true), false)
+}
+
+/// SSIM of each pixel, with the usual gaussian window of 1.5 pixels.
+fn ssim(reference: &Image, image: &Image) -> Vec<f64> {
+    const C1: f64 = 0.01 * 0.01;
+    const C2: f64 = 0.03 * 0.03;
+    let width = reference.width() as usize;
+    let a = plane(reference, |c| display(c).luminance());
+    let b = plane(image, |c| display(c).luminance());
+    let product =
+        |x: &[f64], y: &[f64]| -> Vec<f64> { x.iter().zip(y).map(|(x, y)| x * y).collect() };
+    let window = |values: &[f64]| blur(values, width, 1.5);
+
+    let (mean_a, mean_b) = (window(&a), window(&b));
+    let (mean_aa, mean_bb, mean_ab) = (
+        window(&product(&a, &a)),
+        window(&product(&b, &b)),
+        window(&product(&a, &b)),
+    );
+    (0..a.len())
+        .map(|i| {
+            let (ma, mb) = (mean_a[i], mean_b[i]);
+            let var_a = mean_aa[i] - ma * ma;
+            let var_b = mean_bb[i] - mb * mb;
+            let covariance = mean_ab[i] - ma * mb;
+            ((2.0 * ma * mb + C1) * (2.0 * covariance + C2))
+                / ((ma * ma + mb * mb + C1) * (var_a + var_b + C2))
+        })
+        .collect()
+}
+
+/// CIELAB coordinates of a displayed color, for a D65 white.
+fn lab(display: Color) -> [f64; 3] {
+    let linear = display * display;
+    let xyz = [
+        (0.4124 * linear[0] + 0.3576 * linear[1] + 0.1805 * linear[2]) / 0.95047,
+        linear.luminance(),
+        (0.0193 * linear[0] + 0.1192 * linear[1] + 0.9505 * linear[2]) / 1.08883,
+    ];
+    let f = |t: f64| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(xyz[0]), f(xyz[1]), f(xyz[2]));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Distance between colors with the lightness and the chroma added apart.
+fn hyab(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (a[0] - b[0]).abs() + ((a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// FLIP-like error of each pixel: the difference of the colors blurred as
+/// the eye would, raised as edges and points differ.
+fn flip(reference: &Image, image: &Image) -> Vec<f64> {
+    let width = reference.width() as usize;
+    let height = reference.height() as usize;
+    let labs = |image: &Image| -> Vec<Vec<f64>> {
+        (0..3)
+            .map(|channel| plane(image, |c| lab(display(c))[channel]))
+            .collect()
+    };
+    let (lab_a, lab_b) = (labs(reference), labs(image));
+    let blurred = |lab: &[Vec<f64>]| -> Vec<Vec<f64>> {
+        lab.iter()
+            .map(|channel| blur(channel, width, 1.0))
+            .collect()
+    };
+    let (blurred_a, blurred_b) = (blurred(&lab_a), blurred(&lab_b));
+    let max_distance = hyab(
+        lab(Color::new(0.0, 1.0, 0.0)),
+        lab(Color::new(0.0, 0.0, 1.0)),
+    );
+
+    // Gradient and Laplacian of the lightness between 0 and 1
+    let features = |lightness: &[f64], i: usize| -> (f64, f64) {
+        let (x, y) = (i % width, i / width);
+        let at = |x: usize, y: usize| lightness[y * width + x] / 100.0;
+        let (left, right) = (at(x.saturating_sub(1), y), at((x + 1).min(width - 1), y));
+        let (up, down) = (at(x, y.saturating_sub(1)), at(x, (y + 1).min(height - 1)));
+        let edge = ((right - left).powi(2) + (down - up).powi(2)).sqrt() / 2.0;
+        let point = (left + right + up + down - 4.0 * at(x, y)).abs() / 4.0;
+        (edge, point)
+    };
+
+    (0..width * height)
+        .into_par_iter()
+        .map(|i| {
+            let pixel = |lab: &[Vec<f64>]| [lab[0][i], lab[1][i], lab[2][i]];
+            let color = (hyab(pixel(&blurred_a), pixel(&blurred_b)) / max_distance)
+                .min(1.0)
+                .powf(0.7);
+            let (edge_a, point_a) = features(&lab_a[0], i);
+            let (edge_b, point_b) = features(&lab_b[0], i);
+            let feature = ((edge_a - edge_b).abs().max((point_a - point_b).abs()) / 2f64.sqrt())
+                .min(1.0)
+                .sqrt();
+            color.powf(1.0 - feature)
+        })
+        .collect()
+}
+
+/// Linear color of `error`, between 0 and 1, on a magma-like ramp.
+fn false_color(error: f64) -> Color {
+    const RAMP: [[f64; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [0.23, 0.06, 0.44],
+        [0.72, 0.21, 0.47],
+        [0.99, 0.56, 0.35],
+        [0.99, 0.99, 0.75],
+    ];
+    let t = error.clamp(0.0, 1.0) * (RAMP.len() - 1) as f64;
+    let i = (t as usize).min(RAMP.len() - 2);
+    let (a, b) = (RAMP[i], RAMP[i + 1]);
+    let f = t - i as f64;
+    let display = Color::new(
+        a[0] + (b[0] - a[0]) * f,
+        a[1] + (b[1] - a[1]) * f,
+        a[2] + (b[2] - a[2]) * f,
+    );
+    display * display
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Horizontal gradient with a bright square in the middle.
+    fn image() -> Image {
+        let pixels = (0..32 * 32)
+            .map(|i| {
+                let (x, y) = (i % 32, i / 32);
+                if (12..20).contains(&x) && (12..20).contains(&y) {
+                    Color::new(0.9, 0.8, 0.2)
+                } else {
+                    Color::ones() * (x as f64 / 40.0)
+                }
+            })
+            .collect();
+        Image::new(32, 32, pixels).unwrap()
+    }
+
+    #[test]
+    fn same_image() {
+        let c = compare(&image(), &image()).unwrap();
+        assert_eq!((c.mse, c.rel_mse, c.flip), (0.0, 0.0, 0.0));
+        assert!(c.psnr.is_infinite());
+        assert!(float_eq!(c.ssim, 1.0, 1e-12));
+        assert!(c.difference.pixels().iter().all(|c| c.approx_cero()));
+        for metric in Metric::ALL {
+            assert!(c.passes(metric, c.value(metric)));
+        }
+    }
+
+    #[test]
+    fn metrics_grow_with_the_difference() {
+        let shifted = |offset: f64| {
+            let pixels = image()
+                .pixels()
+                .iter()
+                .map(|&c| c + Color::ones() * offset)
+                .collect();
+            Image::new(32, 32, pixels).unwrap()
+        };
+        let near = compare(&image(), &shifted(0.05)).unwrap();
+        let far = compare(&image(), &shifted(0.1)).unwrap();
+        assert!(float_eq!(far.mse, 0.01, 1e-12));
+        assert!(float_eq!(far.rmse, 0.1, 1e-12));
+        assert!(float_eq!(far.psnr, 20.0, 1e-9));
+        for metric in Metric::ALL {
+            assert!(near.passes(metric, far.value(metric)), "{:?}", metric);
+            assert!(!far.passes(metric, near.value(metric)), "{:?}", metric);
+        }
+        assert!(far.difference.pixel(0, 0)[0] > near.difference.pixel(0, 0)[0]);
+
+        assert!(compare(&image(), &Image::new(1, 1, vec![Color::ones()]).unwrap()).is_err());
+        let empty = Image::new(0, 5, vec![]).unwrap();
+        assert!(compare(&empty, &empty).is_err());
+    }
+
+    #[test]
+    fn metric_names() {
+        for metric in Metric::ALL {
+            assert_eq!(metric.name().parse::<Metric>().unwrap(), metric);
+        }
+        assert_eq!("FLIP".parse::<Metric>().unwrap(), Metric::Flip);
+        assert!("psnr2".parse::<Metric>().is_err());
+    }
+}
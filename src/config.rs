@@ -1,14 +1,30 @@
-use std::default::Default;
+use std::{default::Default, ops::RangeInclusive, sync::Arc, time::Duration};
 
-use crate::{Point, Vec3};
+use crate::{
+    animation::CameraAnimation,
+    cameras::{
+        Camera, CubeMapCamera, EquirectangularCamera, FisheyeCamera, Lens, OmniStereoCamera,
+        OrthographicCamera, PerspectiveCamera, PhysicalCamera, Projection, RealisticCamera,
+        StereoCamera, StereoLayout,
+    },
+    hittables::HitRecord,
+    render::{Aov, CancelToken, Progress, TileOrder, View},
+    Error, Hittable, Point, Ray, Result, Vec3,
+};
+
+/// Rays autofocus tries before giving up on the probed point.
+const PROBE_TRIES: usize = 16;
 
 pub struct RunConfig<'a> {
     pub img_config: ImgConfig,
     pub cam_config: CameraConfig,
     pub scene_config: SceneConfig,
     pub filename: &'a str,
-    pub quiet: bool,
     pub use_bvh: bool,
+    /// Called after every tile, from the thread that rendered it.
+    pub progress: Option<&'a (dyn Fn(&Progress) + Sync)>,
+    /// Stops the render between tiles.
+    pub cancel: Option<CancelToken>,
 }
 
 impl<'a> Default for RunConfig<'a> {
@@ -18,17 +34,191 @@ impl<'a> Default for RunConfig<'a> {
             cam_config: CameraConfig::default(),
             scene_config: SceneConfig::default(),
             filename: "res.png",
-            quiet: false,
             use_bvh: true,
+            progress: None,
+            cancel: None,
+        }
+    }
+}
+
+/// Frames rendered by `run_animation`. Frame `n` starts at `n / fps`
+/// seconds, the time used by animation tracks.
+pub struct AnimationConfig<'a> {
+    pub frames: RangeInclusive<u32>,
+    pub fps: f64,
+    /// Fraction of each frame the shutter stays open, 0.5 being the usual
+    /// 180 degree shutter. 0 disables motion blur.
+    pub shutter: f64,
+    pub camera: CameraAnimation,
+    /// Objects added to the scene, usually `Animated` ones.
+    pub objects: Vec<Arc<dyn Hittable>>,
+    /// Files are named with this prefix, the frame number padded to four
+    /// digits and `.png`.
+    pub prefix: &'a str,
+}
+
+impl<'a> Default for AnimationConfig<'a> {
+    fn default() -> Self {
+        Self {
+            frames: 1..=24,
+            fps: 24.0,
+            shutter: 0.5,
+            camera: CameraAnimation::default(),
+            objects: Vec::new(),
+            prefix: "frame_",
+        }
+    }
+}
+
+/// Limits for `run_progressive`. It stops as soon as any of them is
+/// reached, and at `ImgConfig::samples_per_pixel` samples when none is set.
+pub struct ProgressiveConfig<'a> {
+    pub time_limit: Option<Duration>,
+    pub sample_limit: Option<u32>,
+    /// Average relative error of the pixels, see `Film::error`.
+    pub error_threshold: Option<f64>,
+    /// Samples added to every pixel in each pass. Limits are checked
+    /// between passes.
+    pub samples_per_pass: u32,
+    /// Writes the image so far after a pass when this much time went by
+    /// since the last time.
+    pub write_interval: Option<Duration>,
+    pub checkpoint: Option<CheckpointConfig<'a>>,
+}
+
+impl<'a> Default for ProgressiveConfig<'a> {
+    fn default() -> Self {
+        Self {
+            time_limit: None,
+            sample_limit: None,
+            error_threshold: None,
+            samples_per_pass: 4,
+            write_interval: None,
+            checkpoint: None,
+        }
+    }
+}
+
+/// Where and how often `run_progressive` saves its state, to continue after
+/// being stopped.
+pub struct CheckpointConfig<'a> {
+    pub path: &'a str,
+    /// Saves after a pass when this much time went by since the last save,
+    /// and always once done.
+    pub interval: Duration,
+    /// Continues from the checkpoint at `path` if there is one. The rest of
+    /// the config must be the same as when it was saved.
+    pub resume: bool,
+}
+
+/// Sampling that spends more samples on noisy pixels, see
+/// `Film::pixel_error`.
+pub struct AdaptiveConfig {
+    pub min_samples: u32,
+    pub max_samples: u32,
+    /// Pixels stop getting samples once their relative error is below
+    /// this.
+    pub target_error: f64,
+    /// Also writes a gray image with the samples each pixel got, white
+    /// being `max_samples`.
+    pub samples_filename: Option<String>,
+}
+
+impl Default for AdaptiveConfig {
+    fn default() -> Self {
+        Self {
+            min_samples: 16,
+            max_samples: 1024,
+            target_error: 0.02,
+            samples_filename: None,
+        }
+    }
+}
+
+/// Edge-avoiding à-trous filter run on the image before it's saved, see
+/// `render::denoise`. The `sigma_*` settings say how different neighbors
+/// can be and still be blended in: larger values blur more.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DenoiseConfig {
+    /// Passes of the filter, each reaching twice as far as the last.
+    pub iterations: u32,
+    /// Luminance difference, in standard deviations of the pixel's noise.
+    pub sigma_luminance: f64,
+    /// Exponent for the cosine between normals, which unlike the others is
+    /// stricter the larger it is.
+    pub sigma_normal: f64,
+    /// Depth difference relative to the depth.
+    pub sigma_depth: f64,
+    pub sigma_albedo: f64,
+}
+
+impl Default for DenoiseConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 3,
+            sigma_luminance: 4.0,
+            sigma_normal: 8.0,
+            sigma_depth: 0.1,
+            sigma_albedo: 0.1,
         }
     }
 }
 
+/// Lens effect applied to the linear image by `render::post_process`.
+/// Sizes are in pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostEffect {
+    /// Spreads the light above `threshold` in a gaussian of standard
+    /// deviation `radius`, added back times `intensity`.
+    Bloom {
+        threshold: f64,
+        radius: f64,
+        intensity: f64,
+    },
+    /// Spreads the light above `threshold` in a star of `points` streaks
+    /// that fade in `length`, added back times `intensity`.
+    Glare {
+        threshold: f64,
+        points: u32,
+        length: f64,
+        intensity: f64,
+    },
+    /// Darkens the image away from the center, the corners losing
+    /// `strength` of their light.
+    Vignette { strength: f64 },
+    /// Scales the light of each pixel by a random amount around 1, up to
+    /// `amount` away. The same seed gives the same grain.
+    Grain { amount: f64, seed: u64 },
+    /// Scales the red channel by `1 + shift` away from the center and the
+    /// blue one by `1 - shift`, like a lens that bends them differently.
+    ChromaticAberration { shift: f64 },
+}
+
 pub struct ImgConfig {
     pub aspect_ratio: f64,
     pub width: u32,
+    /// Samples for each pixel, unless `adaptive` is set.
     pub samples_per_pixel: u32,
+    pub adaptive: Option<AdaptiveConfig>,
     pub max_depth: i32,
+    /// Tiles are squares of this many pixels, except at the edges.
+    pub tile_size: u32,
+    pub tile_order: TileOrder,
+    /// Seed for the random numbers of each tile. The same seed gives the
+    /// same image.
+    pub seed: u64,
+    /// Passes `run` writes next to the main image.
+    pub aovs: Vec<Aov>,
+    /// Denoises the image `render` returns and `run` writes.
+    pub denoise: Option<DenoiseConfig>,
+    /// Effects applied in order to that image, after denoising.
+    pub post: Vec<PostEffect>,
+}
+
+impl ImgConfig {
+    pub fn height(&self) -> u32 {
+        (self.width as f64 / self.aspect_ratio) as u32
+    }
 }
 
 impl Default for ImgConfig {
@@ -37,11 +227,19 @@ impl Default for ImgConfig {
             aspect_ratio: 16.0 / 9.0,
             width: 1200,
             samples_per_pixel: 500,
+            adaptive: None,
             max_depth: 50,
+            tile_size: 32,
+            tile_order: TileOrder::Spiral,
+            seed: 0,
+            aovs: Vec::new(),
+            denoise: None,
+            post: Vec::new(),
         }
     }
 }
 
+#[derive(Clone)]
 pub struct CameraConfig {
     pub lookfrom: Point,
     pub lookat: Point,
@@ -49,6 +247,182 @@ pub struct CameraConfig {
     pub vert_fov: f64,
     pub aperture: f64,
     pub focus_dist: f64,
+    pub projection: Projection,
+    pub lens: Lens,
+    /// Takes the field of view, aperture and exposure from a camera body
+    /// and lens instead of `vert_fov` and `aperture`.
+    pub physical: Option<PhysicalCamera>,
+    /// Image point (same coordinates as `Camera::get_ray`) to focus on. The
+    /// focus distance becomes the distance to whatever is seen there, and
+    /// `focus_dist` is only used when nothing is.
+    pub autofocus: Option<(f64, f64)>,
+}
+
+impl CameraConfig {
+    /// Camera for images of `aspect_ratio`. Autofocus looks at `world` as
+    /// it is at `time`. Fails if the projection can't be built, like a lens
+    /// system without elements.
+    pub fn camera(
+        &self,
+        aspect_ratio: f64,
+        world: &dyn Hittable,
+        time: f64,
+    ) -> Result<Arc<dyn Camera>> {
+        let (vert_fov, aperture) = match self.physical {
+            Some(physical) => (physical.vert_fov(aspect_ratio), physical.aperture()),
+            None => (self.vert_fov, self.aperture),
+        };
+        let focus_dist = match self.autofocus {
+            Some((s, t)) => self
+                .probe_focus(s, t, vert_fov, aspect_ratio, world, time)
+                .unwrap_or(self.focus_dist),
+            None => self.focus_dist,
+        };
+
+        self.projected(
+            aspect_ratio,
+            vert_fov,
+            aperture,
+            focus_dist,
+            self.lens.clone(),
+        )
+    }
+
+    fn projected(
+        &self,
+        aspect_ratio: f64,
+        vert_fov: f64,
+        aperture: f64,
+        focus_dist: f64,
+        lens: Lens,
+    ) -> Result<Arc<dyn Camera>> {
+        Ok(match self.projection {
+            Projection::Perspective => Arc::new(
+                PerspectiveCamera::new(
+                    self.lookfrom,
+                    self.lookat,
+                    self.vec_up,
+                    vert_fov,
+                    aspect_ratio,
+                    aperture,
+                    focus_dist,
+                )
+                .with_lens(lens),
+            ),
+            Projection::Orthographic { height } => Arc::new(OrthographicCamera::new(
+                self.lookfrom,
+                self.lookat,
+                self.vec_up,
+                height,
+                aspect_ratio,
+            )),
+            Projection::Fisheye { fov, mapping } => Arc::new(FisheyeCamera::new(
+                self.lookfrom,
+                self.lookat,
+                self.vec_up,
+                fov,
+                aspect_ratio,
+                mapping,
+            )),
+            Projection::Equirectangular => Arc::new(EquirectangularCamera::new(
+                self.lookfrom,
+                self.lookat,
+                self.vec_up,
+            )),
+            Projection::CubeMap => {
+                Arc::new(CubeMapCamera::new(self.lookfrom, self.lookat, self.vec_up))
+            }
+            Projection::Stereo {
+                ipd,
+                convergence,
+                layout,
+            } => {
+                let eye_aspect_ratio = match layout {
+                    StereoLayout::SideBySide => aspect_ratio / 2.0,
+                    StereoLayout::TopBottom => aspect_ratio * 2.0,
+                };
+                Arc::new(
+                    StereoCamera::new(
+                        self.lookfrom,
+                        self.lookat,
+                        self.vec_up,
+                        vert_fov,
+                        eye_aspect_ratio,
+                        aperture,
+                        focus_dist,
+                        ipd,
+                        convergence,
+                        layout,
+                    )
+                    .with_lens(lens),
+                )
+            }
+            Projection::OmniStereo { ipd } => Arc::new(OmniStereoCamera::new(
+                self.lookfrom,
+                self.lookat,
+                self.vec_up,
+                ipd,
+            )),
+            Projection::Realistic {
+                ref elements,
+                film_diagonal,
+                scale,
+            } => Arc::new(RealisticCamera::new(
+                self.lookfrom,
+                self.lookat,
+                self.vec_up,
+                elements.clone(),
+                film_diagonal,
+                aspect_ratio,
+                focus_dist,
+                scale,
+            )?),
+        })
+    }
+
+    /// Scale for the rendered colors, 1 unless a physical camera is used.
+    pub fn exposure(&self) -> f64 {
+        self.physical.map_or(1.0, |physical| physical.exposure())
+    }
+
+    /// Distance along the view direction to what the camera sees at
+    /// `(s, t)` and `time`, looking through the center of its lens.
+    fn probe_focus(
+        &self,
+        s: f64,
+        t: f64,
+        vert_fov: f64,
+        aspect_ratio: f64,
+        world: &dyn Hittable,
+        time: f64,
+    ) -> Option<f64> {
+        let pinhole = Lens {
+            cat_eye: 0.0,
+            ..self.lens.clone()
+        };
+        let camera = self
+            .projected(aspect_ratio, vert_fov, 0.0, self.focus_dist, pinhole)
+            .ok()?;
+        // Lens systems still sample their pupil, and may miss it
+        let ray = (0..PROBE_TRIES).find_map(|_| camera.get_ray(s, t))?;
+        let ray = Ray::with_time(ray.origin(), ray.direction(), time);
+
+        let mut rec = HitRecord::new();
+        if !world.hit(&ray, 0.001, f64::INFINITY, &mut rec) {
+            return None;
+        }
+        let view = self.view();
+        Some((rec.p - view.origin).dot(view.forward))
+    }
+
+    /// Position and view direction, which camera space depth is measured
+    /// along.
+    pub(crate) fn view(&self) -> View {
+        View {
+            origin: self.lookfrom,
+            forward: (self.lookat - self.lookfrom).unit_vector(),
+        }
+    }
 }
 
 impl Default for CameraConfig {
@@ -60,6 +434,10 @@ impl Default for CameraConfig {
             vert_fov: 20.0,
             aperture: 0.1,
             focus_dist: 10.0,
+            projection: Projection::Perspective,
+            lens: Lens::default(),
+            physical: None,
+            autofocus: None,
         }
     }
 }
@@ -68,14 +446,19 @@ pub struct SceneConfig {
     pub small_sphere_count: u32,
     pub diffuse_prob: f64,
     pub metal_prob: f64,
+    /// Seed for placing the spheres, a different scene each time if `None`.
+    pub seed: Option<u64>,
 }
 
 impl SceneConfig {
-    pub fn validate(&self) {
-        assert!(
-            (self.diffuse_prob - self.metal_prob).abs() <= 1.0,
-            "The probabilities for diffuse and metal materials should be <= 1\n(the remainder will be used as the probability for glass)"
-        );
+    pub fn validate(&self) -> Result<()> {
+        let (diffuse, metal) = (self.diffuse_prob, self.metal_prob);
+        if !(diffuse >= 0.0 && metal >= 0.0 && diffuse + metal <= 1.0) {
+            return Err(Error::InvalidConfig(
+                "The probabilities for diffuse and metal materials should be <= 1\n(the remainder will be used as the probability for glass)".to_string(),
+            ));
+        }
+        Ok(())
     }
 }
 
@@ -85,6 +468,84 @@ impl Default for SceneConfig {
             small_sphere_count: 484,
             diffuse_prob: 0.8,
             metal_prob: 0.1,
+            seed: None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        animation::Track,
+        hittables::{Animated, HittableList, Sphere},
+        materials::Lambertian,
+    };
+
+    #[test]
+    fn autofocus_on_probe_hit() {
+        let mut world = HittableList::new();
+        world.add(Arc::new(Sphere::new(
+            Point::new(0.0, 0.0, -5.0),
+            1.0,
+            Arc::new(Lambertian::new(Vec3::ones())),
+        )));
+        let config = CameraConfig {
+            lookfrom: Point::ceros(),
+            lookat: Point::new(0.0, 0.0, -1.0),
+            autofocus: Some((0.5, 0.5)),
+            ..CameraConfig::default()
+        };
+
+        let focus = config.probe_focus(0.5, 0.5, 20.0, 1.0, &world, 0.0);
+        assert!(float_eq!(focus.unwrap(), 4.0, 1e-9));
+        // Nothing to focus on
+        assert!(config
+            .probe_focus(0.5, 1.0, 90.0, 1.0, &world, 0.0)
+            .is_none());
+    }
+
+    #[test]
+    fn autofocus_through_the_lens_at_the_shot_time() {
+        let sphere = Arc::new(Sphere::new(
+            Point::new(0.0, 0.0, -5.0),
+            1.0,
+            Arc::new(Lambertian::new(Vec3::ones())),
+        ));
+        let moving = Animated::new(sphere).with_translation(
+            Track::linear(vec![(0.0, Vec3::ceros()), (1.0, Vec3::new(0.0, 0.0, -3.0))]).unwrap(),
+        );
+        let config = CameraConfig {
+            lookfrom: Point::ceros(),
+            lookat: Point::new(0.0, 0.0, -1.0),
+            lens: Lens {
+                shift: (0.5, 0.0),
+                ..Lens::default()
+            },
+            ..CameraConfig::default()
+        };
+
+        // The shift moves the middle of the view to the left edge
+        assert!(config
+            .probe_focus(0.5, 0.5, 40.0, 1.0, &moving, 0.0)
+            .is_none());
+        let focus = config.probe_focus(0.0, 0.5, 40.0, 1.0, &moving, 0.0);
+        assert!(float_eq!(focus.unwrap(), 4.0, 1e-9));
+        let focus = config.probe_focus(0.0, 0.5, 40.0, 1.0, &moving, 1.0);
+        assert!(float_eq!(focus.unwrap(), 7.0, 1e-9));
+    }
+
+    #[test]
+    fn material_probabilities() {
+        let scene = |diffuse_prob, metal_prob| SceneConfig {
+            diffuse_prob,
+            metal_prob,
+            ..SceneConfig::default()
+        };
+        assert!(scene(0.8, 0.1).validate().is_ok());
+        assert!(scene(0.5, 0.5).validate().is_ok());
+        assert!(scene(0.8, 0.8).validate().is_err());
+        assert!(scene(-0.5, 0.2).validate().is_err());
+        assert!(scene(0.2, f64::NAN).validate().is_err());
+    }
+}
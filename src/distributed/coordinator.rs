@@ -0,0 +1,180 @@
+use std::{
+    collections::VecDeque,
+    io::{self, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Condvar, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::job::job_text;
+use crate::{
+    render::{shot_seed, tile_seed, tiles, Film, Tile},
+    Result, RunConfig,
+};
+
+/// How often the coordinator checks for new workers.
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Workers that take longer than this to send a tile back are taken for
+/// hung, and their tile goes to another.
+const TILE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Tiles waiting for a worker, and how many haven't come back yet.
+struct Queue {
+    pending: VecDeque<usize>,
+    remaining: usize,
+}
+
+/// Renders `config` with the workers that connect to `listener`, and
+/// returns the merged film. Tiles are handed out one at a time, and the
+/// tile of a worker that disconnects goes back to the queue for another.
+///
+/// The result is the same as rendering on this machine with `run`, given a
+/// scene seed.
+pub fn run_coordinator(config: &RunConfig, listener: TcpListener) -> Result<Film> {
+    coordinate(config, listener, TILE_TIMEOUT)
+}
+
+/// `run_coordinator`, giving up on workers after `tile_timeout` without an
+/// answer.
+pub(super) fn coordinate(
+    config: &RunConfig,
+    listener: TcpListener,
+    tile_timeout: Duration,
+) -> Result<Film> {
+    let img_config = &config.img_config;
+    let scene_seed = config.scene_config.seed.unwrap_or_else(rand::random);
+    let job = job_text(config, scene_seed)?;
+
+    let tiles = tiles(
+        img_config.width,
+        img_config.height(),
+        img_config.tile_size,
+        img_config.tile_order,
+    )?;
+    let queue = Mutex::new(Queue {
+        pending: (0..tiles.len()).collect(),
+        remaining: tiles.len(),
+    });
+    let changed = Condvar::new();
+    let finished = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+
+    listener.set_nonblocking(true)?;
+    let mut film = Film::new(img_config.width, img_config.height());
+
+    thread::scope(|scope| {
+        let (job, tiles, queue, changed, finished) = (&job, &tiles, &queue, &changed, &finished);
+        scope.spawn(move || {
+            while !finished.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let sender = sender.clone();
+                        scope.spawn(move || {
+                            serve_worker(
+                                stream,
+                                job,
+                                tiles,
+                                shot_seed(img_config.seed, 0, 0),
+                                tile_timeout,
+                                queue,
+                                changed,
+                                sender,
+                            )
+                        });
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(ACCEPT_INTERVAL)
+                    }
+                    // A failed connection doesn't stop the render
+                    Err(_) => {}
+                }
+            }
+        });
+
+        let merged = (0..tiles.len()).try_for_each(|_| {
+            let (index, tile_film): (usize, Film) = receiver.recv().unwrap();
+            film.merge(&tile_film, tiles[index].x, tiles[index].y)
+        });
+        if merged.is_err() {
+            // Sends the workers still waiting for tiles home
+            let mut queue = queue.lock().unwrap();
+            queue.pending.clear();
+            queue.remaining = 0;
+            changed.notify_all();
+        }
+        finished.store(true, Ordering::Relaxed);
+        merged
+    })?;
+
+    Ok(film)
+}
+
+/// Sends tiles to one worker until there are none left. Whatever goes
+/// wrong with the worker, including taking longer than `timeout` to answer,
+/// its tile is put back in the queue.
+#[allow(clippy::too_many_arguments)]
+fn serve_worker(
+    stream: TcpStream,
+    job: &str,
+    tiles: &[Tile],
+    seed: u64,
+    timeout: Duration,
+    queue: &Mutex<Queue>,
+    changed: &Condvar,
+    results: mpsc::Sender<(usize, Film)>,
+) {
+    let mut current = None;
+    let served = (|| -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(timeout))?;
+        let mut writer = stream.try_clone()?;
+        let mut reader = BufReader::new(stream);
+        writeln!(writer, "{}end", job)?;
+
+        loop {
+            let index = {
+                let mut queue = queue.lock().unwrap();
+                while queue.pending.is_empty() && queue.remaining > 0 {
+                    queue = changed.wait(queue).unwrap();
+                }
+                match queue.pending.pop_front() {
+                    Some(index) => index,
+                    None => {
+                        writer.write_all(b"done\n")?;
+                        return Ok(());
+                    }
+                }
+            };
+            current = Some(index);
+
+            let tile = tiles[index];
+            writeln!(
+                writer,
+                "tile {} {} {} {} {}",
+                tile_seed(seed, index),
+                tile.x,
+                tile.y,
+                tile.width,
+                tile.height
+            )?;
+            let tile_film = Film::read_from(&mut reader, tile.width, tile.height)?;
+
+            current = None;
+            let mut queue = queue.lock().unwrap();
+            queue.remaining -= 1;
+            changed.notify_all();
+            // Only fails once the render is over
+            let _ = results.send((index, tile_film));
+        }
+    })();
+
+    if let (Err(_), Some(index)) = (served, current) {
+        queue.lock().unwrap().pending.push_front(index);
+        changed.notify_all();
+    }
+}
@@ -0,0 +1,121 @@
+mod coordinator;
+mod worker;
+
+pub use coordinator::run_coordinator;
+pub use worker::run_worker;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        build_world,
+        job::job_text,
+        random_scene,
+        render::{render_frame, shot_seed, Monitor, Shot},
+        RunConfig,
+    };
+
+    fn config() -> RunConfig<'static> {
+        let mut config = RunConfig::default();
+        config.img_config.width = 24;
+        config.img_config.samples_per_pixel = 3;
+        config.img_config.max_depth = 4;
+        config.img_config.tile_size = 5;
+        config.scene_config.small_sphere_count = 10;
+        config.scene_config.seed = Some(9);
+        config
+    }
+
+    #[test]
+    fn same_as_local_despite_disconnects() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let coordinator = thread::spawn(move || run_coordinator(&config(), listener).unwrap());
+
+        // Takes a tile and disappears without sending it back
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while !line.starts_with("tile") {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+        drop(reader);
+
+        run_worker(addr, 2).unwrap();
+        let film = coordinator.join().unwrap();
+
+        let config = config();
+        let world =
+            build_world(random_scene(&config.scene_config).unwrap(), config.use_bvh).unwrap();
+        let cameras = [config
+            .cam_config
+            .camera(config.img_config.aspect_ratio, &*world, 0.0)
+            .unwrap()];
+        let shot = Shot {
+            world: &world,
+            cameras: &cameras,
+            views: &[config.cam_config.view()],
+            shutter: (0.0, 0.0),
+            exposure: 1.0,
+        };
+        let local = render_frame(
+            &config.img_config,
+            &shot,
+            shot_seed(config.img_config.seed, 0, 0),
+            &Monitor::default(),
+        )
+        .unwrap();
+        assert_eq!(film.to_rgb8(), local.to_rgb8());
+    }
+
+    #[test]
+    fn hung_workers_lose_their_tile() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let coordinator = thread::spawn(move || {
+            coordinator::coordinate(&config(), listener, Duration::from_millis(200)).unwrap()
+        });
+
+        // Takes a tile and never answers, but keeps the connection open
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        while !line.starts_with("tile") {
+            line.clear();
+            reader.read_line(&mut line).unwrap();
+        }
+
+        run_worker(addr, 1).unwrap();
+        let film = coordinator.join().unwrap();
+        assert_eq!(film.width(), 24);
+        drop(reader);
+    }
+
+    #[test]
+    fn workers_reject_tiles_outside_the_image() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let worker = thread::spawn(move || run_worker(addr, 1));
+
+        let (mut stream, _) = listener.accept().unwrap();
+        write!(
+            stream,
+            "{}end\ntile 1 20 0 10 4\n",
+            job_text(&config(), 9).unwrap()
+        )
+        .unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+
+        assert!(line.starts_with("error"));
+        assert!(worker.join().unwrap().is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::Arc,
+    thread,
+};
+
+use crate::job::Job;
+use crate::{
+    build_world, random_scene,
+    render::{render_tile, Shot, Tile},
+    Camera, Error, Hittable, Result,
+};
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Reads the job the coordinator sends first, up to the `end` line.
+fn read_job(reader: &mut impl BufRead) -> Result<String> {
+    let mut job = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::Parse(
+                "Connection closed before the job was sent".to_string(),
+            ));
+        }
+        if line.trim() == "end" {
+            return Ok(job);
+        }
+        job.push_str(&line);
+    }
+}
+
+/// Renders tiles for the coordinator at `addr` until it says it's done.
+/// Each of the `connections` takes tiles on its own thread, so there should
+/// be about one per core.
+pub fn run_worker<A: ToSocketAddrs>(addr: A, connections: usize) -> Result<()> {
+    let mut streams = Vec::new();
+    let mut job_text = None;
+    for _ in 0..connections.max(1) {
+        let stream = TcpStream::connect(&addr)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let text = read_job(&mut reader)?;
+        if job_text.get_or_insert_with(|| text.clone()) != &text {
+            return Err(Error::Parse(
+                "Got different jobs on each connection".to_string(),
+            ));
+        }
+        streams.push((reader, stream));
+    }
+
+    let job = Job::parse(&job_text.unwrap())?;
+    let world = build_world(random_scene(&job.scene_config)?, job.use_bvh)?;
+    let cameras = [job
+        .cam_config
+        .camera(job.img_config.aspect_ratio, &*world, 0.0)?];
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = streams
+            .into_iter()
+            .map(|(reader, writer)| {
+                let (job, world, cameras) = (&job, &world, &cameras);
+                scope.spawn(move || serve(reader, writer, job, world, cameras))
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().unwrap())
+    })?;
+    Ok(())
+}
+
+fn serve(
+    mut reader: BufReader<TcpStream>,
+    mut writer: TcpStream,
+    job: &Job,
+    world: &Arc<dyn Hittable>,
+    cameras: &[Arc<dyn Camera>],
+) -> io::Result<()> {
+    let samples = job.img_config.samples_per_pixel;
+    let shot = Shot {
+        world,
+        cameras,
+        views: &[job.cam_config.view()],
+        shutter: (0.0, 0.0),
+        exposure: job.cam_config.exposure(),
+    };
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(invalid("Connection closed before the coordinator was done"));
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["done"] => return Ok(()),
+            ["tile", seed, x, y, width, height] => {
+                let seed = seed.parse::<u64>().map_err(|_| invalid("Bad tile seed"))?;
+                let parse = |s: &str| s.parse::<u32>().map_err(|_| invalid("Bad tile"));
+                let tile = Tile {
+                    x: parse(x)?,
+                    y: parse(y)?,
+                    width: parse(width)?,
+                    height: parse(height)?,
+                };
+                if !inside(tile, job.img_config.width, job.img_config.height()) {
+                    // The coordinator takes this for a broken film, and
+                    // gives the tile to someone else
+                    writeln!(writer, "error Tile outside the image")?;
+                    return Err(invalid("Tile outside the image"));
+                }
+
+                let (film, _) = render_tile(&job.img_config, &shot, &|_, _| samples, seed, tile);
+                film.write_to(&mut writer)?;
+                writer.flush()?;
+            }
+            _ => return Err(invalid("Unknown message from the coordinator")),
+        }
+    }
+}
+
+/// Whether `tile` isn't empty and fits in a `width` by `height` image.
+fn inside(tile: Tile, width: u32, height: u32) -> bool {
+    let fits = |at: u32, size: u32, total: u32| {
+        size > 0 && at.checked_add(size).is_some_and(|end| end <= total)
+    };
+    fits(tile.x, tile.width, width) && fits(tile.y, tile.height, height)
+}
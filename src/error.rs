@@ -0,0 +1,43 @@
+use std::{fmt, io};
+
+/// Everything that can go wrong with input given to the crate.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file or connection failed.
+    Io(io::Error),
+    /// Text that doesn't hold what it should, like a vector that isn't
+    /// three numbers.
+    Parse(String),
+    /// Settings that can't be rendered.
+    InvalidConfig(String),
+    /// An object without a bounding box was put in a BVH.
+    NoBoundingBox,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse(msg) => write!(f, "Parse error: {}", msg),
+            Error::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
+            Error::NoBoundingBox => write!(f, "Object without a bounding box in a BVH"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
@@ -0,0 +1,317 @@
+//! Golden image tests: small scenes, one for each material and primitive,
+//! rendered with few samples and checked against references rendered with
+//! many and kept in `tests/golden`. Setting `UPDATE_GOLDEN` renders the
+//! references again, to run after changes that are meant to change the
+//! images.
+
+use std::{env, f64::consts::PI, path::PathBuf, sync::Arc};
+
+use crate::{
+    hittables::{
+        Cone, Csg, CsgOp, Cuboid, Cylinder, Disk, Heightfield, HittableList, Hyperboloid,
+        Paraboloid, Plane, Quad, Sdf, SdfShape, Sphere, Torus, AABB,
+    },
+    materials::{Dielectric, Lambertian, Metal},
+    render::{render_frame, Film, Monitor, Shot},
+    CameraConfig, Color, Hittable, Image, ImgConfig, Material, Point, Vec3,
+};
+
+const SIZE: u32 = 32;
+const SAMPLES: u32 = 64;
+const REFERENCE_SAMPLES: u32 = 4096;
+
+/// Pixels further than this many standard deviations from the reference.
+const OUTLIER_Z: f64 = 5.0;
+
+fn render(object: Arc<dyn Hittable>, samples_per_pixel: u32, seed: u64) -> Film {
+    let mut scene = HittableList::new();
+    scene.add(object);
+    scene.add(Arc::new(Plane::new(
+        Point::new(0.0, -1.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+    )));
+    let world: Arc<dyn Hittable> = Arc::new(scene);
+    let cam_config = CameraConfig {
+        lookfrom: Point::new(0.0, 1.5, 5.0),
+        lookat: Point::new(0.0, -0.2, 0.0),
+        vert_fov: 35.0,
+        aperture: 0.0,
+        ..CameraConfig::default()
+    };
+    let cameras = [cam_config.camera(1.0, &*world, 0.0).unwrap()];
+    let shot = Shot {
+        world: &world,
+        cameras: &cameras,
+        views: &[cam_config.view()],
+        shutter: (0.0, 0.0),
+        exposure: 1.0,
+    };
+    let img_config = ImgConfig {
+        width: SIZE,
+        aspect_ratio: 1.0,
+        samples_per_pixel,
+        max_depth: 8,
+        ..ImgConfig::default()
+    };
+    render_frame(&img_config, &shot, seed, &Monitor::default()).unwrap()
+}
+
+/// Renders `object` and checks it against its reference, pixel by pixel
+/// and as a whole, in standard deviations of the noise.
+fn check(name: &str, object: Arc<dyn Hittable>) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden"]
+        .iter()
+        .collect::<PathBuf>()
+        .join(format!("{}.pfm", name));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        let reference = render(object.clone(), REFERENCE_SAMPLES, 1);
+        reference.to_image().save_pfm(&path).unwrap();
+    }
+    let reference = Image::load(&path).unwrap_or_else(|e| {
+        panic!(
+            "Can't load {} ({}), run with UPDATE_GOLDEN set to render it",
+            path.display(),
+            e
+        )
+    });
+
+    let film = render(object, SAMPLES, 2);
+    let (mut z2_sum, mut outliers) = (0.0, 0);
+    let (mut difference, mut variance) = (0.0, 0.0);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let d = film.pixel(x, y).luminance() - reference.pixel(x, y).luminance();
+            // The reference has a little noise too, and pixels that always
+            // see the same color none
+            let v = film.variance(x, y) * (1.0 + SAMPLES as f64 / REFERENCE_SAMPLES as f64) + 1e-8;
+            z2_sum += d * d / v;
+            if d * d > OUTLIER_Z * OUTLIER_Z * v {
+                outliers += 1;
+            }
+            difference += d;
+            variance += v;
+        }
+    }
+
+    // Variances from few samples are often too small, which makes squared
+    // z values average more than 1 and a few pixels seem far off
+    let pixels = (SIZE * SIZE) as f64;
+    let z2_mean = z2_sum / pixels;
+    assert!(
+        z2_mean < 2.5 && (outliers as f64) <= pixels * 0.01,
+        "{} differs from its reference: squared z of the pixels averages {}, {} are outliers",
+        name,
+        z2_mean,
+        outliers
+    );
+    let z = difference / variance.sqrt();
+    assert!(
+        z.abs() < 4.0,
+        "{} is off from its reference by {} standard deviations",
+        name,
+        z
+    );
+}
+
+fn color(r: f64, g: f64, b: f64) -> Arc<dyn Material> {
+    Arc::new(Lambertian::new(Color::new(r, g, b)))
+}
+
+fn up() -> Vec3 {
+    Vec3::new(0.0, 1.0, 0.0)
+}
+
+fn sphere(material: Arc<dyn Material>) -> Arc<dyn Hittable> {
+    Arc::new(Sphere::new(Point::ceros(), 1.0, material))
+}
+
+#[test]
+fn lambertian() {
+    check("lambertian", sphere(color(0.7, 0.3, 0.3)));
+}
+
+#[test]
+fn metal() {
+    check(
+        "metal",
+        sphere(Arc::new(Metal::new(Color::new(0.8, 0.6, 0.2), 0.0))),
+    );
+}
+
+#[test]
+fn fuzzy_metal() {
+    check(
+        "fuzzy_metal",
+        sphere(Arc::new(Metal::new(Color::new(0.8, 0.8, 0.8), 0.4))),
+    );
+}
+
+#[test]
+fn dielectric() {
+    check("dielectric", sphere(Arc::new(Dielectric::new(1.5))));
+}
+
+#[test]
+fn cuboid() {
+    check(
+        "cuboid",
+        Arc::new(Cuboid::new(
+            Point::new(-0.8, -1.0, -0.8),
+            Point::new(0.8, 0.6, 0.8),
+            color(0.3, 0.5, 0.7),
+        )),
+    );
+}
+
+#[test]
+fn cylinder() {
+    check(
+        "cylinder",
+        Arc::new(Cylinder::new(
+            Point::new(0.0, -1.0, 0.0),
+            up(),
+            0.8,
+            1.6,
+            true,
+            color(0.3, 0.7, 0.3),
+        )),
+    );
+}
+
+#[test]
+fn cone() {
+    check(
+        "cone",
+        Arc::new(Cone::new(
+            Point::new(0.0, -1.0, 0.0),
+            up(),
+            0.9,
+            1.8,
+            true,
+            color(0.7, 0.5, 0.2),
+        )),
+    );
+}
+
+#[test]
+fn disk() {
+    check(
+        "disk",
+        Arc::new(Disk::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 1.0),
+            1.0,
+            color(0.6, 0.3, 0.6),
+        )),
+    );
+}
+
+#[test]
+fn quad() {
+    check(
+        "quad",
+        Arc::new(Quad::new(
+            Point::new(-1.0, -1.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, -0.5),
+            color(0.2, 0.6, 0.6),
+        )),
+    );
+}
+
+#[test]
+fn torus() {
+    check(
+        "torus",
+        Arc::new(Torus::new(
+            Point::new(0.0, -0.3, 0.0),
+            Vec3::new(0.0, 1.0, 0.5),
+            0.8,
+            0.3,
+            color(0.7, 0.7, 0.3),
+        )),
+    );
+}
+
+#[test]
+fn paraboloid() {
+    check(
+        "paraboloid",
+        Arc::new(Paraboloid::new(
+            Point::new(0.0, -1.0, 0.0),
+            up(),
+            0.9,
+            1.5,
+            true,
+            color(0.4, 0.4, 0.8),
+        )),
+    );
+}
+
+#[test]
+fn hyperboloid() {
+    check(
+        "hyperboloid",
+        Arc::new(Hyperboloid::new(
+            Point::new(0.0, -1.0, 0.0),
+            up(),
+            0.4,
+            0.8,
+            1.8,
+            true,
+            color(0.8, 0.4, 0.4),
+        )),
+    );
+}
+
+#[test]
+fn csg() {
+    let cube = Arc::new(Cuboid::new(
+        Point::new(-0.8, -0.8, -0.8),
+        Point::new(0.8, 0.8, 0.8),
+        color(0.5, 0.5, 0.8),
+    ));
+    let ball = Arc::new(Sphere::new(Point::ceros(), 1.05, color(0.8, 0.5, 0.5)));
+    check("csg", Arc::new(Csg::new(cube, ball, CsgOp::Difference)));
+}
+
+#[test]
+fn sdf() {
+    let shape = Sdf::round_box(Vec3::new(0.5, 0.5, 0.5), 0.2)
+        .smooth_union(Sdf::sphere(0.5).translate(Vec3::new(0.0, 0.8, 0.0)), 0.3)
+        .twist(0.5);
+    let bbox = AABB::new(Point::new(-1.2, -1.0, -1.2), Point::new(1.2, 1.5, 1.2));
+    check(
+        "sdf",
+        Arc::new(SdfShape::new(shape, bbox, color(0.3, 0.6, 0.4))),
+    );
+}
+
+#[test]
+fn heightfield() {
+    let n = 16;
+    let heights = (0..n * n)
+        .map(|i| {
+            let (x, z) = (
+                (i % n) as f64 / (n - 1) as f64,
+                (i / n) as f64 / (n - 1) as f64,
+            );
+            0.5 + 0.5 * (2.0 * PI * x).sin() * (2.0 * PI * z).cos()
+        })
+        .collect();
+    check(
+        "heightfield",
+        Arc::new(
+            Heightfield::new(
+                heights,
+                n,
+                n,
+                Point::new(-1.2, -1.0, -1.2),
+                Vec3::new(2.4, 0.8, 2.4),
+                color(0.6, 0.5, 0.4),
+            )
+            .unwrap(),
+        ),
+    );
+}
@@ -11,6 +11,40 @@ impl AABB {
         Self { max, min }
     }
 
+    /// Smallest box containing all of `points`.
+    pub fn from_points(points: &[Point]) -> Self {
+        let mut min = Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for p in points {
+            for a in 0..3 {
+                min[a] = min[a].min(p[a]);
+                max[a] = max[a].max(p[a]);
+            }
+        }
+
+        Self { max, min }
+    }
+
+    /// Like `hit`, but returns the part of `t_min..t_max` inside the box.
+    pub fn hit_range(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> Option<(f64, f64)> {
+        for a in 0..3 {
+            let inv_d = 1.0 / ray.direction()[a];
+            let mut t0 = (self.min[a] - ray.origin()[a]) * inv_d;
+            let mut t1 = (self.max[a] - ray.origin()[a]) * inv_d;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            t_min = t_min.max(t0);
+            t_max = t_max.min(t1);
+            if t_max <= t_min {
+                return None;
+            }
+        }
+
+        Some((t_min, t_max))
+    }
+
     pub fn hit(&self, ray: &Ray, mut t_min: f64, mut t_max: f64) -> bool {
         for a in 0..3 {
             let mut t0 = (self.min[a] - ray.origin()[a]) / ray.direction()[a];
@@ -29,6 +63,21 @@ impl AABB {
         true
     }
 
+    /// Grows any axis thinner than `delta` so that flat primitives still have
+    /// a box with volume, otherwise the slab test can miss them entirely.
+    pub fn pad(&self, delta: f64) -> AABB {
+        let mut min = self.min;
+        let mut max = self.max;
+        for a in 0..3 {
+            if max[a] - min[a] < delta {
+                min[a] -= delta / 2.0;
+                max[a] += delta / 2.0;
+            }
+        }
+
+        AABB::new(min, max)
+    }
+
     pub fn surrounding_box(b0: &AABB, b1: &AABB) -> AABB {
         let small = Point::new(
             b0.min.x().min(b1.min.x()),
@@ -0,0 +1,222 @@
+use std::sync::Arc;
+
+use super::AABB;
+use crate::{animation::Track, Error, HitRecord, Hittable, Point, Ray, Result, Vec3};
+
+/// Extra times sampled between keyframes to bound the motion, since splines
+/// can overshoot their keys.
+const BOUNDING_STEPS: usize = 32;
+
+/// Moves another object over time. At `ray.time()` the object is scaled,
+/// rotated around the y axis (in degrees) and then translated. Rays cast at
+/// different times during the shutter give motion blur.
+pub struct Animated {
+    object: Arc<dyn Hittable>,
+    translation: Track<Vec3>,
+    rotation_y: Track<f64>,
+    scale: Track<f64>,
+}
+
+impl Animated {
+    pub fn new(object: Arc<dyn Hittable>) -> Self {
+        Self {
+            object,
+            translation: Track::constant(Vec3::ceros()),
+            rotation_y: Track::constant(0.0),
+            scale: Track::constant(1.0),
+        }
+    }
+
+    pub fn with_translation(mut self, translation: Track<Vec3>) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    pub fn with_rotation_y(mut self, rotation_y: Track<f64>) -> Self {
+        self.rotation_y = rotation_y;
+        self
+    }
+
+    /// Fails unless the scale stays positive, since a zero scale collapses
+    /// the object and a negative one turns it inside out.
+    pub fn with_scale(mut self, scale: Track<f64>) -> Result<Self> {
+        // Splines can dip below their keys, so the curve is checked too
+        let (start, end) = scale.range();
+        if steps(start, end).chain(scale.times()).any(|time| {
+            let s = scale.sample(time);
+            !s.is_finite() || s <= 0.0
+        }) {
+            return Err(Error::InvalidConfig(
+                "Animated scale must stay positive".to_string(),
+            ));
+        }
+
+        self.scale = scale;
+        Ok(self)
+    }
+
+    /// Object space point `p` in world space at `time`.
+    fn to_world(&self, p: Point, time: f64) -> Point {
+        let angle = self.rotation_y.sample(time).to_radians();
+        rotate_y(p * self.scale.sample(time), angle) + self.translation.sample(time)
+    }
+}
+
+/// `BOUNDING_STEPS` evenly spaced times from `start` to `end`.
+fn steps(start: f64, end: f64) -> impl Iterator<Item = f64> {
+    (0..=BOUNDING_STEPS).map(move |i| start + (end - start) * i as f64 / BOUNDING_STEPS as f64)
+}
+
+fn rotate_y(v: Vec3, angle: f64) -> Vec3 {
+    let (sin, cos) = angle.sin_cos();
+    Vec3::new(cos * v.x() + sin * v.z(), v.y(), -sin * v.x() + cos * v.z())
+}
+
+impl Hittable for Animated {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let time = ray.time();
+        let angle = self.rotation_y.sample(time).to_radians();
+        let scale = self.scale.sample(time);
+
+        // Same t in both spaces, since the transform is affine
+        let origin = rotate_y(ray.origin() - self.translation.sample(time), -angle) / scale;
+        let direction = rotate_y(ray.direction(), -angle) / scale;
+        let local = Ray::with_time(origin, direction, time);
+
+        if !self.object.hit(&local, t_min, t_max, rec) {
+            return false;
+        }
+
+        rec.p = ray.at(rec.t);
+        rec.normal = rotate_y(rec.normal, angle);
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        let mut local = AABB::new(Point::ceros(), Point::ceros());
+        if !self.object.bounding_box(&mut local) {
+            return false;
+        }
+
+        let (start, end) = [
+            self.translation.range(),
+            self.rotation_y.range(),
+            self.scale.range(),
+        ]
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(s, e), r| {
+            (s.min(r.0), e.max(r.1))
+        });
+        let times = steps(start, end)
+            .chain(self.translation.times())
+            .chain(self.rotation_y.times())
+            .chain(self.scale.times());
+
+        let mut corners = Vec::new();
+        for time in times {
+            for i in 0..8 {
+                let corner = Point::new(
+                    if i & 1 == 0 {
+                        local.min.x()
+                    } else {
+                        local.max.x()
+                    },
+                    if i & 2 == 0 {
+                        local.min.y()
+                    } else {
+                        local.max.y()
+                    },
+                    if i & 4 == 0 {
+                        local.min.z()
+                    } else {
+                        local.max.z()
+                    },
+                );
+                corners.push(self.to_world(corner, time));
+            }
+        }
+
+        *output_box = AABB::from_points(&corners);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hittables::Sphere, materials::Lambertian, Color};
+
+    fn moving_sphere() -> Animated {
+        let sphere = Sphere::new(
+            Point::ceros(),
+            1.0,
+            Arc::new(Lambertian::new(Color::ones())),
+        );
+        Animated::new(Arc::new(sphere)).with_translation(
+            Track::linear(vec![
+                (0.0, Vec3::new(0.0, 0.0, 0.0)),
+                (1.0, Vec3::new(4.0, 0.0, 0.0)),
+            ])
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn hit_follows_time() {
+        let animated = moving_sphere();
+        let mut rec = HitRecord::new();
+
+        let at = |time| Ray::with_time(Point::new(4.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0), time);
+        assert!(!animated.hit(&at(0.0), 0.0, f64::INFINITY, &mut rec));
+        assert!(animated.hit(&at(1.0), 0.0, f64::INFINITY, &mut rec));
+        assert!(rec.p.approx_eq_epsilon(Point::new(4.0, 1.0, 0.0), 1e-9));
+        assert!(rec.normal.approx_eq_epsilon(Vec3::new(0.0, 1.0, 0.0), 1e-9));
+    }
+
+    #[test]
+    fn rotated_and_scaled() {
+        let sphere = Sphere::new(
+            Point::new(1.0, 0.0, 0.0),
+            0.5,
+            Arc::new(Lambertian::new(Color::ones())),
+        );
+        let animated = Animated::new(Arc::new(sphere))
+            .with_rotation_y(Track::constant(90.0))
+            .with_scale(Track::constant(2.0))
+            .unwrap();
+        let mut rec = HitRecord::new();
+
+        // +x goes to -z, scaled twice as far and big
+        let ray = Ray::new(Point::new(0.0, 5.0, -2.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(animated.hit(&ray, 0.0, f64::INFINITY, &mut rec));
+        assert!(rec.p.approx_eq_epsilon(Point::new(0.0, 1.0, -2.0), 1e-9));
+        assert!(rec.normal.approx_eq_epsilon(Vec3::new(0.0, 1.0, 0.0), 1e-9));
+    }
+
+    #[test]
+    fn box_covers_motion() {
+        let mut bbox = AABB::new(Point::ceros(), Point::ceros());
+        assert!(moving_sphere().bounding_box(&mut bbox));
+        assert!(bbox
+            .min
+            .approx_eq_epsilon(Point::new(-1.0, -1.0, -1.0), 1e-9));
+        assert!(bbox.max.approx_eq_epsilon(Point::new(5.0, 1.0, 1.0), 1e-9));
+    }
+
+    #[test]
+    fn scale_stays_positive() {
+        assert!(moving_sphere().with_scale(Track::constant(0.0)).is_err());
+        assert!(moving_sphere()
+            .with_scale(Track::linear(vec![(0.0, 1.0), (1.0, -1.0)]).unwrap())
+            .is_err());
+        // Positive keys, but the spline overshoots below zero between them
+        assert!(moving_sphere()
+            .with_scale(
+                Track::spline(vec![(0.0, 4.0), (1.0, 1.0), (2.0, 0.02), (3.0, 0.02)]).unwrap()
+            )
+            .is_err());
+        assert!(moving_sphere()
+            .with_scale(Track::linear(vec![(0.0, 0.5), (1.0, 2.0)]).unwrap())
+            .is_ok());
+    }
+}
@@ -1,8 +1,8 @@
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::sync::Arc;
 
 use super::{Hittable, HittableList, AABB};
-use crate::Point;
+use crate::{random, Error, Point, Result};
 
 pub struct BVH {
     left: Arc<dyn Hittable>,
@@ -11,13 +11,31 @@ pub struct BVH {
 }
 
 impl BVH {
-    pub fn new(objects: &mut [Arc<dyn Hittable>]) -> Self {
-        let axis = thread_rng().gen_range(0..3);
+    /// Fails if there are no objects or any of them has no bounding box.
+    pub fn new(objects: &mut [Arc<dyn Hittable>]) -> Result<Self> {
+        if objects.is_empty() {
+            return Err(Error::InvalidConfig(
+                "A BVH needs at least one object".to_string(),
+            ));
+        }
+        if objects
+            .iter()
+            .any(|o| !o.bounding_box(&mut AABB::new(Point::ceros(), Point::ceros())))
+        {
+            return Err(Error::NoBoundingBox);
+        }
+        Ok(Self::build(objects))
+    }
+
+    pub fn from_hittable_list(list: &mut HittableList) -> Result<Self> {
+        Self::new(&mut list.objects[..])
+    }
+
+    fn build(objects: &mut [Arc<dyn Hittable>]) -> Self {
+        let axis = random::with_rng(|rng| rng.gen_range(0..3));
         let key = move |a: &Arc<dyn Hittable>| {
             let mut bbox_a = AABB::new(Point::ceros(), Point::ceros());
-            if !a.bounding_box(&mut bbox_a) {
-                panic!("No bounding box on BVH creation");
-            }
+            a.bounding_box(&mut bbox_a);
             bbox_a.min[axis].round() as i64
         };
 
@@ -41,26 +59,21 @@ impl BVH {
             _ => {
                 objects.sort_by_cached_key(key);
                 let mid = objects.len() / 2;
-                left = Arc::new(BVH::new(&mut objects[0..mid]));
-                right = Arc::new(BVH::new(&mut objects[mid..]));
+                left = Arc::new(BVH::build(&mut objects[0..mid]));
+                right = Arc::new(BVH::build(&mut objects[mid..]));
             }
         }
 
+        // Every object was checked by `new` to have one
         let mut left_box = AABB::new(Point::ceros(), Point::ceros());
         let mut right_box = AABB::new(Point::ceros(), Point::ceros());
-
-        if !left.bounding_box(&mut left_box) || !right.bounding_box(&mut right_box) {
-            panic!("No bounding box on BVH construction");
-        }
+        left.bounding_box(&mut left_box);
+        right.bounding_box(&mut right_box);
 
         let bbox = AABB::surrounding_box(&left_box, &right_box);
 
         Self { left, right, bbox }
     }
-
-    pub fn from_hittable_list(list: &mut HittableList) -> Self {
-        Self::new(&mut list.objects[..])
-    }
 }
 
 impl Hittable for BVH {
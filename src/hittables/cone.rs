@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use super::{quadric::Quadric, AABB};
+use crate::{materials::Material, HitRecord, Hittable, Point, Vec3};
+
+/// Cone with a base of `radius` at `base` and its apex `height` units along
+/// `axis`.
+pub struct Cone {
+    surface: Quadric,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let k = radius * radius / (height * height);
+        Self {
+            surface: Quadric::new(
+                base,
+                axis,
+                (k, -2.0 * k * height, radius * radius),
+                height,
+                capped,
+                false,
+                material,
+            ),
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &crate::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.surface.hit(ray, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        self.surface.bounding_box(output_box)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color, Ray};
+
+    #[test]
+    fn slanted_normal() {
+        let cone = Cone::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            1.0,
+            1.0,
+            false,
+            Arc::new(Lambertian::new(Color::ones())),
+        );
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(5.0, 0.5, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+
+        assert!(cone.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 4.5, 1e-9));
+        let expected = Vec3::new(1.0, 1.0, 0.0).unit_vector();
+        assert!(rec.normal.approx_eq_epsilon(expected, 1e-9));
+
+        // Above the apex there is nothing, not even the mirrored nappe
+        let ray = Ray::new(Point::new(5.0, 1.5, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        assert!(!cone.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+    }
+}
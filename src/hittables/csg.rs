@@ -0,0 +1,270 @@
+use std::sync::Arc;
+
+use super::{HitRecord, Hittable, AABB};
+use crate::{Point, Ray};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    /// Left minus right.
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOp::Union => in_left || in_right,
+            CsgOp::Intersection => in_left && in_right,
+            CsgOp::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Boolean combination of two closed objects.
+///
+/// Both children are queried with `hit_all` and their crossings are merged
+/// along the ray, keeping only the ones where being inside the combined
+/// shape changes.
+pub struct Csg {
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+    op: CsgOp,
+}
+
+impl Csg {
+    pub fn new(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>, op: CsgOp) -> Self {
+        Self { left, right, op }
+    }
+
+    pub fn union(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        Self::new(left, right, CsgOp::Union)
+    }
+
+    pub fn intersection(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        Self::new(left, right, CsgOp::Intersection)
+    }
+
+    pub fn difference(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        Self::new(left, right, CsgOp::Difference)
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let mut hits = Vec::new();
+        self.hit_all(ray, t_min, t_max, &mut hits);
+
+        match hits.into_iter().next() {
+            Some(first) => {
+                *rec = first;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        let mut left_box = AABB::new(Point::ceros(), Point::ceros());
+        let mut right_box = AABB::new(Point::ceros(), Point::ceros());
+        let has_left = self.left.bounding_box(&mut left_box);
+        let has_right = self.right.bounding_box(&mut right_box);
+
+        match self.op {
+            CsgOp::Union => {
+                if !has_left || !has_right {
+                    return false;
+                }
+                *output_box = AABB::surrounding_box(&left_box, &right_box);
+            }
+            CsgOp::Intersection => {
+                *output_box = match (has_left, has_right) {
+                    (true, true) => {
+                        let mut overlap = left_box;
+                        for a in 0..3 {
+                            overlap.min[a] = left_box.min[a].max(right_box.min[a]);
+                            overlap.max[a] =
+                                left_box.max[a].min(right_box.max[a]).max(overlap.min[a]);
+                        }
+                        overlap
+                    }
+                    (true, false) => left_box,
+                    (false, true) => right_box,
+                    (false, false) => return false,
+                };
+            }
+            CsgOp::Difference => {
+                if !has_left {
+                    return false;
+                }
+                *output_box = left_box;
+            }
+        }
+
+        true
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord>) {
+        // The children are followed past `t_max`, otherwise a ray inside one
+        // that leaves it further away would seem to start outside it
+        let mut left_hits = Vec::new();
+        let mut right_hits = Vec::new();
+        self.left.hit_all(ray, t_min, f64::INFINITY, &mut left_hits);
+        self.right
+            .hit_all(ray, t_min, f64::INFINITY, &mut right_hits);
+
+        // A ray that starts inside an object leaves it on its first crossing
+        let mut in_left = left_hits.first().is_some_and(|h| !h.front_face);
+        let mut in_right = right_hits.first().is_some_and(|h| !h.front_face);
+        let mut inside = self.op.inside(in_left, in_right);
+
+        let mut left_hits = left_hits.into_iter().peekable();
+        let mut right_hits = right_hits.into_iter().peekable();
+        loop {
+            let (from_left, t) = match (left_hits.peek(), right_hits.peek()) {
+                (Some(l), Some(r)) if l.t <= r.t => (true, l.t),
+                (_, Some(r)) => (false, r.t),
+                (Some(l), None) => (true, l.t),
+                (None, None) => break,
+            };
+            if t > t_max {
+                break;
+            }
+
+            let mut rec = if from_left {
+                let rec = left_hits.next().unwrap();
+                in_left = rec.front_face;
+                rec
+            } else {
+                let rec = right_hits.next().unwrap();
+                in_right = rec.front_face;
+                rec
+            };
+
+            let now_inside = self.op.inside(in_left, in_right);
+            if now_inside != inside {
+                // The normal already faces the ray, only which side of the
+                // combined surface we are on can change.
+                rec.front_face = now_inside;
+                hits.push(rec);
+                inside = now_inside;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittables::{Cuboid, Sphere},
+        materials::Lambertian,
+        Color, Vec3,
+    };
+
+    fn sphere(center: Point, radius: f64) -> Arc<dyn Hittable> {
+        Arc::new(Sphere::new(
+            center,
+            radius,
+            Arc::new(Lambertian::new(Color::ones())),
+        ))
+    }
+
+    fn crossings(object: &dyn Hittable, ray: &Ray) -> Vec<(f64, bool)> {
+        let mut hits = Vec::new();
+        object.hit_all(ray, 0.001, f64::INFINITY, &mut hits);
+        hits.iter().map(|h| (h.t, h.front_face)).collect()
+    }
+
+    fn assert_crossings(found: Vec<(f64, bool)>, expected: &[(f64, bool)]) {
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for (f, e) in found.iter().zip(expected.iter()) {
+            assert!(float_eq!(f.0, e.0, 1e-6), "{:?} != {:?}", found, expected);
+            assert_eq!(f.1, e.1, "{:?} != {:?}", found, expected);
+        }
+    }
+
+    #[test]
+    fn lens() {
+        let lens = Csg::intersection(
+            sphere(Point::new(-1.0, 0.0, 0.0), 2.0),
+            sphere(Point::new(1.0, 0.0, 0.0), 2.0),
+        );
+        let ray = Ray::new(Point::new(-10.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+
+        assert_crossings(crossings(&lens, &ray), &[(9.0, true), (11.0, false)]);
+
+        let mut bbox = AABB::new(Point::ceros(), Point::ceros());
+        assert!(lens.bounding_box(&mut bbox));
+        assert!(float_eq!(bbox.min.x(), -1.0, 1e-9));
+        assert!(float_eq!(bbox.max.x(), 1.0, 1e-9));
+    }
+
+    #[test]
+    fn union() {
+        let blob = Csg::union(
+            sphere(Point::new(-1.0, 0.0, 0.0), 2.0),
+            sphere(Point::new(1.0, 0.0, 0.0), 2.0),
+        );
+        let ray = Ray::new(Point::new(-10.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+
+        assert_crossings(crossings(&blob, &ray), &[(7.0, true), (13.0, false)]);
+    }
+
+    #[test]
+    fn hollowed_cube() {
+        let material = Arc::new(Lambertian::new(Color::ones()));
+        let cube: Arc<dyn Hittable> = Arc::new(Cuboid::new(
+            Point::new(-1.0, -1.0, -1.0),
+            Point::new(1.0, 1.0, 1.0),
+            material,
+        ));
+        let hollow = Csg::difference(cube, sphere(Point::ceros(), 0.5));
+        let ray = Ray::new(Point::new(-10.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+
+        assert_crossings(
+            crossings(&hollow, &ray),
+            &[(9.0, true), (9.5, false), (10.5, true), (11.0, false)],
+        );
+
+        // Carved surface faces into the hole
+        let mut rec = HitRecord::new();
+        assert!(hollow.hit(&ray, 9.2, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 9.5, 1e-6));
+        assert!(!rec.front_face);
+        assert!(rec
+            .normal
+            .approx_eq_epsilon(Vec3::new(-1.0, 0.0, 0.0), 1e-9));
+    }
+
+    #[test]
+    fn starting_inside() {
+        let hollow = Csg::difference(
+            sphere(Point::ceros(), 2.0),
+            sphere(Point::new(0.0, 0.0, 0.0), 1.0),
+        );
+        let ray = Ray::new(Point::new(1.5, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+
+        assert_crossings(crossings(&hollow, &ray), &[(0.5, false)]);
+    }
+
+    #[test]
+    fn stopping_before_the_exit() {
+        let carved = Csg::difference(
+            sphere(Point::ceros(), 5.0),
+            sphere(Point::new(3.0, 0.0, 0.0), 1.0),
+        );
+        let ray = Ray::new(Point::ceros(), Vec3::new(1.0, 0.0, 0.0));
+
+        // Inside the big sphere, which it only leaves at 5
+        let mut hits = Vec::new();
+        carved.hit_all(&ray, 0.001, 3.0, &mut hits);
+        let found = hits.iter().map(|h| (h.t, h.front_face)).collect();
+        assert_crossings(found, &[(2.0, false)]);
+
+        let mut rec = HitRecord::new();
+        assert!(carved.hit(&ray, 0.001, 3.0, &mut rec));
+        assert!(float_eq!(rec.t, 2.0, 1e-6));
+        assert!(!carved.hit(&ray, 0.001, 1.5, &mut rec));
+    }
+}
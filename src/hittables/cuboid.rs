@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use super::{quad::FLAT_PADDING, HittableList, Quad, AABB};
+use crate::{materials::Material, HitRecord, Hittable, Point, Vec3};
+
+/// Axis-aligned box with opposite corners `a` and `b`, made of six quads with
+/// outward facing normals.
+pub struct Cuboid {
+    sides: HittableList,
+    bbox: AABB,
+}
+
+impl Cuboid {
+    pub fn new(a: Point, b: Point, material: Arc<dyn Material>) -> Self {
+        let bbox = AABB::from_points(&[a, b]);
+        let min = bbox.min;
+        let max = bbox.max;
+
+        let dx = Vec3::new(max.x() - min.x(), 0.0, 0.0);
+        let dy = Vec3::new(0.0, max.y() - min.y(), 0.0);
+        let dz = Vec3::new(0.0, 0.0, max.z() - min.z());
+
+        let mut sides = HittableList::new();
+        // front, right, back, left, top, bottom
+        sides.add(Arc::new(Quad::new(
+            Point::new(min.x(), min.y(), max.z()),
+            dx,
+            dy,
+            material.clone(),
+        )));
+        sides.add(Arc::new(Quad::new(
+            Point::new(max.x(), min.y(), max.z()),
+            -dz,
+            dy,
+            material.clone(),
+        )));
+        sides.add(Arc::new(Quad::new(
+            Point::new(max.x(), min.y(), min.z()),
+            -dx,
+            dy,
+            material.clone(),
+        )));
+        sides.add(Arc::new(Quad::new(
+            Point::new(min.x(), min.y(), min.z()),
+            dz,
+            dy,
+            material.clone(),
+        )));
+        sides.add(Arc::new(Quad::new(
+            Point::new(min.x(), max.y(), max.z()),
+            dx,
+            -dz,
+            material.clone(),
+        )));
+        sides.add(Arc::new(Quad::new(
+            Point::new(min.x(), min.y(), min.z()),
+            dx,
+            dz,
+            material,
+        )));
+
+        Self { sides, bbox }
+    }
+}
+
+impl Hittable for Cuboid {
+    fn hit(&self, ray: &crate::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if !self.bbox.pad(FLAT_PADDING).hit(ray, t_min, t_max) {
+            return false;
+        }
+
+        self.sides.hit(ray, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        *output_box = self.bbox.pad(FLAT_PADDING);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color, Ray};
+
+    #[test]
+    fn outward_normals() {
+        let cuboid = Cuboid::new(
+            Point::new(1.0, 1.0, 1.0),
+            Point::new(-1.0, -1.0, -1.0),
+            Arc::new(Lambertian::new(Color::ones())),
+        );
+        let dirs = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, -1.0, 0.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.0, 0.0, -1.0),
+        ];
+
+        for dir in dirs.iter() {
+            let mut rec = HitRecord::new();
+            let ray = Ray::new(*dir * 5.0, -*dir);
+
+            assert!(cuboid.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+            assert!(float_eq!(rec.t, 4.0, 1e-12));
+            assert!(rec.front_face);
+            assert!(rec.normal.approx_eq_epsilon(*dir, 1e-12));
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use super::{quadric::Quadric, AABB};
+use crate::{materials::Material, HitRecord, Hittable, Point, Vec3};
+
+/// Cylinder of `radius` going from `base` along `axis` for `height` units.
+pub struct Cylinder {
+    surface: Quadric,
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Point,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            surface: Quadric::new(
+                base,
+                axis,
+                (0.0, 0.0, radius * radius),
+                height,
+                capped,
+                capped,
+                material,
+            ),
+        }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &crate::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.surface.hit(ray, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        self.surface.bounding_box(output_box)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color, Ray};
+
+    fn cylinder(capped: bool) -> Cylinder {
+        Cylinder::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            1.0,
+            2.0,
+            capped,
+            Arc::new(Lambertian::new(Color::ones())),
+        )
+    }
+
+    #[test]
+    fn side_hit() {
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(5.0, 1.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+
+        assert!(cylinder(false).hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 4.0, 1e-9));
+        assert!(rec.normal.approx_eq_epsilon(Vec3::new(1.0, 0.0, 0.0), 1e-9));
+        assert!(float_eq!(rec.v, 0.5, 1e-9));
+    }
+
+    #[test]
+    fn caps() {
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(0.5, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+
+        assert!(cylinder(true).hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 3.0, 1e-9));
+        assert!(rec.front_face);
+        assert!(rec.normal.approx_eq_epsilon(Vec3::new(0.0, 1.0, 0.0), 1e-9));
+
+        // Without caps the ray goes straight through the open ends
+        assert!(!cylinder(false).hit(&ray, 0.001, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut bbox = AABB::new(Point::ceros(), Point::ceros());
+
+        assert!(cylinder(true).bounding_box(&mut bbox));
+        assert!(bbox
+            .min
+            .approx_eq_epsilon(Point::new(-1.0, 0.0, -1.0), 1e-9));
+        assert!(bbox.max.approx_eq_epsilon(Point::new(1.0, 2.0, 1.0), 1e-9));
+    }
+}
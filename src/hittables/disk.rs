@@ -0,0 +1,126 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{quad::FLAT_PADDING, AABB};
+use crate::{materials::Material, onb::Onb, HitRecord, Hittable, Point, Vec3};
+
+/// Flat circular disk facing `normal`. `u` is the angle around the center and
+/// `v` the distance to it, relative to the radius.
+pub struct Disk {
+    center: Point,
+    radius: f64,
+    frame: Onb,
+    material: Arc<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Point, normal: Vec3, radius: f64, material: Arc<dyn Material>) -> Self {
+        Self {
+            center,
+            radius,
+            frame: Onb::from_w(normal),
+            material,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &crate::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let normal = self.frame.w();
+        let denom = normal.dot(ray.direction());
+        if float_eq_cero!(denom, 1e-8) {
+            return false;
+        }
+
+        let t = normal.dot(self.center - ray.origin()) / denom;
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        let p = ray.at(t);
+        let local = self.frame.to_local(p - self.center);
+        let dist2 = local.x() * local.x() + local.y() * local.y();
+        if dist2 > self.radius * self.radius {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = (local.y().atan2(local.x()) + PI) / (2.0 * PI);
+        rec.v = dist2.sqrt() / self.radius;
+        rec.set_face_normal(ray, normal);
+        rec.material = Arc::clone(&self.material);
+
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        let normal = self.frame.w();
+        let extent = Vec3::new(
+            self.radius * (1.0 - normal.x() * normal.x()).max(0.0).sqrt(),
+            self.radius * (1.0 - normal.y() * normal.y()).max(0.0).sqrt(),
+            self.radius * (1.0 - normal.z() * normal.z()).max(0.0).sqrt(),
+        );
+        *output_box = AABB::new(self.center - extent, self.center + extent).pad(FLAT_PADDING);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color, Ray};
+
+    fn facing_up() -> Disk {
+        Disk::new(
+            Point::new(1.0, 0.0, 1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            2.0,
+            Arc::new(Lambertian::new(Color::ones())),
+        )
+    }
+
+    #[test]
+    fn hit_uv() {
+        let disk = facing_up();
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(1.0, 3.0, 1.0), Vec3::new(0.0, -1.0, 0.0));
+
+        assert!(disk.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 3.0, 1e-12));
+        assert!(float_eq!(rec.v, 0.0, 1e-12));
+        assert!(rec.front_face);
+
+        let edge = Ray::new(Point::new(2.0, 3.0, 1.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(disk.hit(&edge, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.v, 0.5, 1e-12));
+        assert!((0.0..=1.0).contains(&rec.u));
+    }
+
+    #[test]
+    fn miss_outside() {
+        let disk = facing_up();
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(2.5, 3.0, 2.5), Vec3::new(0.0, -1.0, 0.0));
+
+        assert!(!disk.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn tilted_box() {
+        let disk = Disk::new(
+            Point::ceros(),
+            Vec3::new(1.0, 1.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::new(Color::ones())),
+        );
+        let mut bbox = AABB::new(Point::ceros(), Point::ceros());
+
+        assert!(disk.bounding_box(&mut bbox));
+        let half = 0.5f64.sqrt();
+        assert!(float_eq!(bbox.max.x(), half, 1e-9));
+        assert!(float_eq!(bbox.max.y(), half, 1e-9));
+        assert!(float_eq!(bbox.max.z(), 1.0, 1e-9));
+        assert!(float_eq!(bbox.min.z(), -1.0, 1e-9));
+    }
+}
@@ -0,0 +1,429 @@
+use std::{fs::File, io::Read, path::Path, sync::Arc};
+
+use super::{quad::FLAT_PADDING, AABB};
+use crate::{image_io, materials::Material, Error, HitRecord, Hittable, Point, Ray, Result, Vec3};
+
+/// Terrain made of a regular grid of height samples, split into two
+/// triangles per cell and shaded with interpolated vertex normals.
+///
+/// The grid covers `size.x()` by `size.z()` world units starting at
+/// `origin`, and a height of 1 is lifted `size.y()` units above it. Rays are
+/// walked cell by cell with a 2D DDA, skipping cells whose height range the
+/// ray passes over or under.
+pub struct Heightfield {
+    origin: Point,
+    size: Vec3,
+    nx: usize,
+    nz: usize,
+    heights: Vec<f64>,
+    normals: Vec<Vec3>,
+    bbox: AABB,
+    material: Arc<dyn Material>,
+}
+
+fn check_samples(nx: usize, nz: usize) -> Result<()> {
+    if nx < 2 || nz < 2 {
+        return Err(Error::InvalidConfig(format!(
+            "A heightfield needs at least 2x2 samples, found {}x{}",
+            nx, nz
+        )));
+    }
+    Ok(())
+}
+
+impl Heightfield {
+    /// `heights` holds `nx * nz` samples in `[0, 1]`, in rows of constant
+    /// `z` going from `origin.x()` to `origin.x() + size.x()`.
+    pub fn new(
+        heights: Vec<f64>,
+        nx: usize,
+        nz: usize,
+        origin: Point,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Result<Self> {
+        check_samples(nx, nz)?;
+        if nx.checked_mul(nz) != Some(heights.len()) {
+            return Err(Error::InvalidConfig(format!(
+                "A {}x{} heightfield needs {} samples, found {}",
+                nx,
+                nz,
+                nx.saturating_mul(nz),
+                heights.len()
+            )));
+        }
+
+        let (min, max) = heights
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &h| {
+                (min.min(h), max.max(h))
+            });
+        let bbox = AABB::new(
+            Point::new(origin.x(), origin.y() + min * size.y(), origin.z()),
+            Point::new(
+                origin.x() + size.x(),
+                origin.y() + max * size.y(),
+                origin.z() + size.z(),
+            ),
+        )
+        .pad(FLAT_PADDING);
+
+        let mut field = Self {
+            origin,
+            size,
+            nx,
+            nz,
+            heights,
+            normals: Vec::new(),
+            bbox,
+            material,
+        };
+        field.normals = field.vertex_normals();
+        Ok(field)
+    }
+
+    /// Loads a grayscale PNG, one sample per pixel. 16 bit images keep their
+    /// full precision, color images use the average of their channels.
+    pub fn from_png<P: AsRef<Path>>(
+        path: P,
+        origin: Point,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Result<Self> {
+        let (nx, nz, heights) = image_io::read_grayscale_png(path)?;
+        Self::new(heights, nx, nz, origin, size, material)
+    }
+
+    /// Loads headerless little endian 16 bit samples, as exported by most
+    /// terrain tools.
+    pub fn from_raw16<P: AsRef<Path>>(
+        path: P,
+        nx: usize,
+        nz: usize,
+        origin: Point,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Result<Self> {
+        check_samples(nx, nz)?;
+        let mut bytes = Vec::new();
+        File::open(path)?.read_to_end(&mut bytes)?;
+        let expected = nx.checked_mul(nz).and_then(|n| n.checked_mul(2));
+        if expected != Some(bytes.len()) {
+            return Err(Error::Parse(format!(
+                "Expected {} bytes for a {}x{} heightfield, found {}",
+                nx.saturating_mul(nz).saturating_mul(2),
+                nx,
+                nz,
+                bytes.len()
+            )));
+        }
+
+        let heights = bytes
+            .chunks(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as f64 / u16::MAX as f64)
+            .collect();
+
+        Self::new(heights, nx, nz, origin, size, material)
+    }
+
+    fn cell_size(&self) -> (f64, f64) {
+        (
+            self.size.x() / (self.nx - 1) as f64,
+            self.size.z() / (self.nz - 1) as f64,
+        )
+    }
+
+    fn height(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.nx + i]
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Point {
+        let (dx, dz) = self.cell_size();
+        Point::new(
+            self.origin.x() + i as f64 * dx,
+            self.origin.y() + self.height(i, j) * self.size.y(),
+            self.origin.z() + j as f64 * dz,
+        )
+    }
+
+    fn vertex_normals(&self) -> Vec<Vec3> {
+        let (dx, dz) = self.cell_size();
+        let mut normals = Vec::with_capacity(self.nx * self.nz);
+        for j in 0..self.nz {
+            for i in 0..self.nx {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.nx - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.nz - 1));
+                let slope_x = (self.height(i1, j) - self.height(i0, j)) * self.size.y()
+                    / ((i1 - i0) as f64 * dx);
+                let slope_z = (self.height(i, j1) - self.height(i, j0)) * self.size.y()
+                    / ((j1 - j0) as f64 * dz);
+                normals.push(Vec3::new(-slope_x, 1.0, -slope_z).unit_vector());
+            }
+        }
+        normals
+    }
+
+    /// Möller-Trumbore, returning `t` and the barycentric coordinates of the
+    /// second and third vertices.
+    fn hit_triangle(ray: &Ray, v0: Point, v1: Point, v2: Point) -> Option<(f64, f64, f64)> {
+        let e1 = v1 - v0;
+        let e2 = v2 - v0;
+        let p = ray.direction().cross(e2);
+        let det = e1.dot(p);
+        if float_eq_cero!(det, 1e-12) {
+            return None;
+        }
+
+        let inv_det = 1.0 / det;
+        let s = ray.origin() - v0;
+        let b1 = s.dot(p) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+        let q = s.cross(e1);
+        let b2 = ray.direction().dot(q) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        Some((e2.dot(q) * inv_det, b1, b2))
+    }
+
+    fn hit_cell(
+        &self,
+        ray: &Ray,
+        i: usize,
+        j: usize,
+        t_min: f64,
+        t_max: f64,
+        rec: &mut HitRecord,
+    ) -> bool {
+        let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        let triangles = [[0, 1, 3], [1, 2, 3]];
+
+        let mut closest = t_max;
+        let mut hit = None;
+        for tri in triangles.iter() {
+            let (a, b, c) = (corners[tri[0]], corners[tri[1]], corners[tri[2]]);
+            let found = Heightfield::hit_triangle(
+                ray,
+                self.vertex(a.0, a.1),
+                self.vertex(b.0, b.1),
+                self.vertex(c.0, c.1),
+            );
+            if let Some((t, b1, b2)) = found {
+                if t >= t_min && t <= closest {
+                    closest = t;
+                    hit = Some((a, b, c, b1, b2));
+                }
+            }
+        }
+
+        match hit {
+            Some((a, b, c, b1, b2)) => {
+                let n = |(i, j): (usize, usize)| self.normals[j * self.nx + i];
+                let normal = (n(a) * (1.0 - b1 - b2) + n(b) * b1 + n(c) * b2).unit_vector();
+
+                rec.t = closest;
+                rec.p = ray.at(closest);
+                rec.u = ((rec.p.x() - self.origin.x()) / self.size.x()).clamp(0.0, 1.0);
+                rec.v = ((rec.p.z() - self.origin.z()) / self.size.z()).clamp(0.0, 1.0);
+                rec.set_face_normal(ray, normal);
+                rec.material = Arc::clone(&self.material);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn cell_range(&self, i: usize, j: usize) -> (f64, f64) {
+        let h = [
+            self.height(i, j),
+            self.height(i + 1, j),
+            self.height(i, j + 1),
+            self.height(i + 1, j + 1),
+        ];
+        let min = h.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = h.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        (
+            self.origin.y() + min * self.size.y(),
+            self.origin.y() + max * self.size.y(),
+        )
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let (t_start, t_end) = match self.bbox.hit_range(ray, t_min, t_max) {
+            Some(range) => range,
+            None => return false,
+        };
+
+        let (dx, dz) = self.cell_size();
+        let grid = |p: Point| {
+            (
+                (p.x() - self.origin.x()) / dx,
+                (p.z() - self.origin.z()) / dz,
+            )
+        };
+        let last_i = (self.nx - 2) as i64;
+        let last_j = (self.nz - 2) as i64;
+
+        let (gx, gz) = grid(ray.at(t_start));
+        let mut i = (gx.floor() as i64).clamp(0, last_i);
+        let mut j = (gz.floor() as i64).clamp(0, last_j);
+
+        // Ray direction in cells per unit of `t`
+        let (gdx, gdz) = (ray.direction().x() / dx, ray.direction().z() / dz);
+        let step_i = if gdx > 0.0 { 1 } else { -1 };
+        let step_j = if gdz > 0.0 { 1 } else { -1 };
+        let delta_i = (1.0 / gdx).abs();
+        let delta_j = (1.0 / gdz).abs();
+        let next_boundary = |cell: i64, step: i64, g: f64, gd: f64| {
+            let edge = (cell + if step > 0 { 1 } else { 0 }) as f64;
+            if gd == 0.0 {
+                f64::INFINITY
+            } else {
+                t_start + (edge - g) / gd
+            }
+        };
+        let mut t_next_i = next_boundary(i, step_i, gx, gdx);
+        let mut t_next_j = next_boundary(j, step_j, gz, gdz);
+
+        let mut t_cell = t_start;
+        loop {
+            let t_exit = t_next_i.min(t_next_j).min(t_end);
+
+            let (low, high) = self.cell_range(i as usize, j as usize);
+            let y_enter = ray.at(t_cell).y();
+            let y_exit = ray.at(t_exit).y();
+            let above = y_enter.min(y_exit) > high;
+            let below = y_enter.max(y_exit) < low;
+            if !above && !below && self.hit_cell(ray, i as usize, j as usize, t_min, t_max, rec) {
+                return true;
+            }
+
+            if t_exit >= t_end {
+                return false;
+            }
+
+            if t_next_i < t_next_j {
+                i += step_i;
+                t_next_i += delta_i;
+            } else {
+                j += step_j;
+                t_next_j += delta_j;
+            }
+            if i < 0 || i > last_i || j < 0 || j > last_j {
+                return false;
+            }
+            t_cell = t_exit;
+        }
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        *output_box = self.bbox;
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color};
+
+    fn material() -> Arc<dyn Material> {
+        Arc::new(Lambertian::new(Color::ones()))
+    }
+
+    /// 5x5 field rising along `x`, from 0 to 1 over 4 units.
+    fn ramp() -> Heightfield {
+        let heights = (0..25).map(|k| (k % 5) as f64 / 4.0).collect();
+        Heightfield::new(
+            heights,
+            5,
+            5,
+            Point::ceros(),
+            Vec3::new(4.0, 1.0, 4.0),
+            material(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn wrong_sample_count() {
+        let new = |heights: Vec<f64>, nx, nz| {
+            Heightfield::new(heights, nx, nz, Point::ceros(), Vec3::ones(), material())
+        };
+        assert!(new(vec![0.0; 4], 1, 4).is_err());
+        assert!(new(vec![0.0; 5], 2, 2).is_err());
+        assert!(new(vec![0.0; 4], usize::MAX, 2).is_err());
+        assert!(new(vec![0.0; 4], 2, 2).is_ok());
+    }
+
+    #[test]
+    fn vertical_ray() {
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(2.5, 10.0, 1.3), Vec3::new(0.0, -1.0, 0.0));
+
+        assert!(ramp().hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.p.y(), 2.5 / 4.0, 1e-9));
+        assert!(rec
+            .normal
+            .approx_eq_epsilon(Vec3::new(-0.25, 1.0, 0.0).unit_vector(), 1e-9));
+        assert!(float_eq!(rec.u, 2.5 / 4.0, 1e-9));
+        assert!(float_eq!(rec.v, 1.3 / 4.0, 1e-9));
+    }
+
+    #[test]
+    fn grazing_ray() {
+        let field = ramp();
+        let mut rec = HitRecord::new();
+
+        // Travels along the ramp crossing many cells before hitting it
+        let ray = Ray::new(Point::new(-1.0, 0.6, 0.5), Vec3::new(1.0, 0.0, 0.1));
+        assert!(field.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.p.x(), 2.4, 1e-9));
+
+        // Stays above the field the whole way
+        let ray = Ray::new(Point::new(-1.0, 1.1, 0.5), Vec3::new(1.0, 0.0, 0.1));
+        assert!(!field.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn png_and_raw_agree() {
+        let dir = std::env::temp_dir();
+        let png_path = dir.join("ray_tracing_heightfield.png");
+        let raw_path = dir.join("ray_tracing_heightfield.r16");
+
+        let samples: Vec<u16> = (0..12).map(|k| k * 5000).collect();
+        {
+            let file = File::create(&png_path).unwrap();
+            let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), 4, 3);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Sixteen);
+            let mut writer = encoder.write_header().unwrap();
+            let data: Vec<u8> = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+            writer.write_image_data(&data).unwrap();
+        }
+        let raw: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        std::fs::write(&raw_path, raw).unwrap();
+
+        let size = Vec3::new(3.0, 2.0, 2.0);
+        let from_png = Heightfield::from_png(&png_path, Point::ceros(), size, material()).unwrap();
+        let from_raw =
+            Heightfield::from_raw16(&raw_path, 4, 3, Point::ceros(), size, material()).unwrap();
+
+        for (a, b) in from_png.heights.iter().zip(from_raw.heights.iter()) {
+            assert!(float_eq!(a, b));
+        }
+        assert!(float_eq!(from_png.height(3, 2), 55000.0 / 65535.0));
+        assert!(
+            Heightfield::from_raw16(&raw_path, 5, 3, Point::ceros(), size, material()).is_err()
+        );
+
+        std::fs::remove_file(png_path).unwrap();
+        std::fs::remove_file(raw_path).unwrap();
+    }
+}
@@ -11,8 +11,15 @@ pub struct HitRecord {
     pub p: Point,
     pub normal: Vec3,
     pub t: f64,
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
     pub material: Arc<dyn Material>,
+    /// Set by `Tagged` for the ID passes. Every new surface hit starts at 0
+    /// in `set_face_normal`, so untagged objects never keep the IDs of an
+    /// earlier hit.
+    pub object_id: u32,
+    pub material_id: u32,
 }
 
 impl HitRecord {
@@ -21,12 +28,18 @@ impl HitRecord {
             p: Point::ceros(),
             normal: Vec3::ceros(),
             t: 0.0,
+            u: 0.0,
+            v: 0.0,
             front_face: false,
             material: Arc::new(Lambertian::new(Color::ceros())),
+            object_id: 0,
+            material_id: 0,
         }
     }
 
     pub fn set_face_normal(&mut self, ray: &Ray, outward_normal: Vec3) {
+        self.object_id = 0;
+        self.material_id = 0;
         self.front_face = ray.direction().dot(outward_normal) < 0.0;
         self.normal = if self.front_face {
             outward_normal
@@ -35,3 +48,9 @@ impl HitRecord {
         }
     }
 }
+
+impl Default for HitRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
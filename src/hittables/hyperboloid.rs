@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use super::{quadric::Quadric, AABB};
+use crate::{materials::Material, HitRecord, Hittable, Point, Vec3};
+
+/// Hyperboloid of one sheet going from `base` along `axis` for `height`
+/// units. It is `waist_radius` wide halfway and `end_radius` at both ends;
+/// with ends narrower than the waist it bulges out like a barrel instead.
+pub struct Hyperboloid {
+    surface: Quadric,
+}
+
+impl Hyperboloid {
+    pub fn new(
+        base: Point,
+        axis: Vec3,
+        waist_radius: f64,
+        end_radius: f64,
+        height: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let half = height / 2.0;
+        let k = (end_radius * end_radius - waist_radius * waist_radius) / (half * half);
+        Self {
+            surface: Quadric::new(
+                base,
+                axis,
+                (
+                    k,
+                    -2.0 * k * half,
+                    waist_radius * waist_radius + k * half * half,
+                ),
+                height,
+                capped,
+                capped,
+                material,
+            ),
+        }
+    }
+}
+
+impl Hittable for Hyperboloid {
+    fn hit(&self, ray: &crate::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.surface.hit(ray, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        self.surface.bounding_box(output_box)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color, Ray};
+
+    fn hyperboloid(waist_radius: f64, end_radius: f64) -> Hyperboloid {
+        Hyperboloid::new(
+            Point::ceros(),
+            Vec3::new(0.0, 1.0, 0.0),
+            waist_radius,
+            end_radius,
+            2.0,
+            false,
+            Arc::new(Lambertian::new(Color::ones())),
+        )
+    }
+
+    fn side_t(shape: &Hyperboloid, y: f64) -> f64 {
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(5.0, y, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        assert!(shape.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        rec.t
+    }
+
+    #[test]
+    fn waist_and_ends() {
+        let shape = hyperboloid(0.5, 1.0);
+        assert!(float_eq!(side_t(&shape, 1.0), 4.5, 1e-9));
+        assert!(float_eq!(side_t(&shape, 0.001), 4.0, 1e-3));
+        assert!(float_eq!(side_t(&shape, 1.999), 4.0, 1e-3));
+    }
+
+    #[test]
+    fn bounding_box() {
+        // Narrow ends bulge out at the waist, which the box must still hold
+        for &(waist, end) in [(0.5, 1.0), (1.0, 0.5)].iter() {
+            let shape = hyperboloid(waist, end);
+            let mut bbox = AABB::new(Point::ceros(), Point::ceros());
+
+            assert!(shape.bounding_box(&mut bbox));
+            assert!(bbox
+                .min
+                .approx_eq_epsilon(Point::new(-1.0, 0.0, -1.0), 1e-9));
+            assert!(bbox.max.approx_eq_epsilon(Point::new(1.0, 2.0, 1.0), 1e-9));
+            assert!(float_eq!(side_t(&shape, 1.0), 5.0 - waist, 1e-9));
+        }
+    }
+}
@@ -1,18 +1,66 @@
 use crate::ray::Ray;
 
 mod aabb;
+mod animated;
 mod bvh;
+mod cone;
+mod csg;
+mod cuboid;
+mod cylinder;
+mod disk;
+mod heightfield;
 mod hit_record;
 mod hittable_list;
+mod hyperboloid;
+mod paraboloid;
+mod plane;
+mod quad;
+mod quadric;
+mod sdf;
 mod sphere;
+mod tagged;
+mod torus;
 
 pub use aabb::AABB;
+pub use animated::Animated;
 pub use bvh::BVH;
+pub use cone::Cone;
+pub use csg::{Csg, CsgOp};
+pub use cuboid::Cuboid;
+pub use cylinder::Cylinder;
+pub use disk::Disk;
+pub use heightfield::Heightfield;
 pub use hit_record::HitRecord;
 pub use hittable_list::HittableList;
+pub use hyperboloid::Hyperboloid;
+pub use paraboloid::Paraboloid;
+pub use plane::Plane;
+pub use quad::Quad;
+pub use sdf::{Sdf, SdfShape};
 pub use sphere::Sphere;
+pub use tagged::Tagged;
+pub use torus::Torus;
+
+/// Gap left after each crossing when `hit_all` walks along the ray, so the
+/// same crossing is not found twice.
+const CROSSING_EPSILON: f64 = 1e-7;
 
 pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool;
     fn bounding_box(&self, output_box: &mut AABB) -> bool;
+
+    /// Appends every crossing of the surface between `t_min` and `t_max` to
+    /// `hits`, sorted by `t`. `front_face` tells entries (`true`) apart from
+    /// exits (`false`), which is only meaningful for closed objects.
+    ///
+    /// The default implementation calls `hit` repeatedly, moving `t_min`
+    /// past the last crossing found.
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord>) {
+        let mut t_min = t_min;
+        let mut rec = HitRecord::new();
+        while self.hit(ray, t_min, t_max, &mut rec) {
+            t_min = rec.t + CROSSING_EPSILON;
+            hits.push(rec.clone());
+        }
+    }
 }
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use super::{quadric::Quadric, AABB};
+use crate::{materials::Material, HitRecord, Hittable, Point, Vec3};
+
+/// Paraboloid with its vertex at `base`, opening along `axis` until it
+/// reaches `radius` at `height`.
+pub struct Paraboloid {
+    surface: Quadric,
+}
+
+impl Paraboloid {
+    pub fn new(
+        base: Point,
+        axis: Vec3,
+        radius: f64,
+        height: f64,
+        capped: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            surface: Quadric::new(
+                base,
+                axis,
+                (0.0, radius * radius / height, 0.0),
+                height,
+                false,
+                capped,
+                material,
+            ),
+        }
+    }
+}
+
+impl Hittable for Paraboloid {
+    fn hit(&self, ray: &crate::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        self.surface.hit(ray, t_min, t_max, rec)
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        self.surface.bounding_box(output_box)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color, Ray};
+
+    fn bowl() -> Paraboloid {
+        Paraboloid::new(
+            Point::ceros(),
+            Vec3::new(0.0, 1.0, 0.0),
+            1.0,
+            1.0,
+            true,
+            Arc::new(Lambertian::new(Color::ones())),
+        )
+    }
+
+    #[test]
+    fn side_hit() {
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(5.0, 0.25, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+
+        assert!(bowl().hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 4.5, 1e-9));
+        let normal = Vec3::new(1.0, -1.0, 0.0).unit_vector();
+        assert!(rec.normal.approx_eq_epsilon(normal, 1e-9));
+        assert!(float_eq!(rec.v, 0.25, 1e-9));
+    }
+
+    #[test]
+    fn cap_and_vertex() {
+        let mut rec = HitRecord::new();
+        let down = Ray::new(Point::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(bowl().hit(&down, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 4.0, 1e-9));
+        assert!(rec.normal.approx_eq_epsilon(Vec3::new(0.0, 1.0, 0.0), 1e-9));
+
+        // Only the open end has a cap
+        let up = Ray::new(Point::new(0.0, -5.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(bowl().hit(&up, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 5.0, 1e-9));
+        assert!(rec
+            .normal
+            .approx_eq_epsilon(Vec3::new(0.0, -1.0, 0.0), 1e-9));
+    }
+
+    #[test]
+    fn bounding_box() {
+        let mut bbox = AABB::new(Point::ceros(), Point::ceros());
+
+        assert!(bowl().bounding_box(&mut bbox));
+        assert!(bbox
+            .min
+            .approx_eq_epsilon(Point::new(-1.0, 0.0, -1.0), 1e-9));
+        assert!(bbox.max.approx_eq_epsilon(Point::new(1.0, 1.0, 1.0), 1e-9));
+    }
+}
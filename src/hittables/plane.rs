@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use super::AABB;
+use crate::{materials::Material, onb::Onb, HitRecord, Hittable, Point, Vec3};
+
+/// Infinite plane through `point`. The UVs tile every `uv_scale` world units.
+///
+/// A plane has no bounding box, so it can't go inside a `BVH`; `build_world`
+/// leaves it in the `HittableList` next to it.
+pub struct Plane {
+    point: Point,
+    frame: Onb,
+    uv_scale: f64,
+    material: Arc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point, normal: Vec3, material: Arc<dyn Material>) -> Self {
+        Self::with_uv_scale(point, normal, 1.0, material)
+    }
+
+    pub fn with_uv_scale(
+        point: Point,
+        normal: Vec3,
+        uv_scale: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            point,
+            frame: Onb::from_w(normal),
+            uv_scale,
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &crate::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let normal = self.frame.w();
+        let denom = normal.dot(ray.direction());
+        if float_eq_cero!(denom, 1e-8) {
+            return false;
+        }
+
+        let t = normal.dot(self.point - ray.origin()) / denom;
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = ray.at(t);
+        let local = self.frame.to_local(rec.p - self.point) / self.uv_scale;
+        rec.u = local.x().rem_euclid(1.0);
+        rec.v = local.y().rem_euclid(1.0);
+        rec.set_face_normal(ray, normal);
+        rec.material = Arc::clone(&self.material);
+
+        true
+    }
+
+    fn bounding_box(&self, _output_box: &mut AABB) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color, Ray};
+
+    fn ground() -> Plane {
+        Plane::with_uv_scale(
+            Point::new(0.0, 1.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            2.0,
+            Arc::new(Lambertian::new(Color::ones())),
+        )
+    }
+
+    #[test]
+    fn hit_from_both_sides() {
+        let plane = ground();
+        let mut rec = HitRecord::new();
+
+        let down = Ray::new(Point::new(0.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(plane.hit(&down, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 2.0, 1e-12));
+        assert!(rec.front_face);
+
+        let up = Ray::new(Point::new(0.0, -1.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(plane.hit(&up, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 2.0, 1e-12));
+        assert!(!rec.front_face);
+        assert!(rec.normal.approx_eq(Vec3::new(0.0, -1.0, 0.0)));
+    }
+
+    #[test]
+    fn miss_parallel_and_behind() {
+        let plane = ground();
+        let mut rec = HitRecord::new();
+
+        let parallel = Ray::new(Point::new(0.0, 3.0, 0.0), Vec3::new(1.0, 0.0, 0.0));
+        assert!(!plane.hit(&parallel, 0.001, f64::INFINITY, &mut rec));
+        let away = Ray::new(Point::new(0.0, 3.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        assert!(!plane.hit(&away, 0.001, f64::INFINITY, &mut rec));
+        let down = Ray::new(Point::new(0.0, 3.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(!plane.hit(&down, 0.001, 1.5, &mut rec));
+    }
+
+    #[test]
+    fn uvs_tile() {
+        let plane = ground();
+        let mut rec = HitRecord::new();
+        let mut uv = |x, z| {
+            let ray = Ray::new(Point::new(x, 3.0, z), Vec3::new(0.0, -1.0, 0.0));
+            assert!(plane.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+            (rec.u, rec.v)
+        };
+
+        let (u0, v0) = uv(0.5, 0.5);
+        let (u1, v1) = uv(4.5, -3.5);
+        assert!(float_eq!(u0, u1, 1e-9) && float_eq!(v0, v1, 1e-9));
+        assert!((0.0..1.0).contains(&u0) && (0.0..1.0).contains(&v0));
+    }
+
+    #[test]
+    fn no_bounding_box() {
+        let mut bbox = AABB::new(Point::ceros(), Point::ceros());
+        assert!(!ground().bounding_box(&mut bbox));
+    }
+}
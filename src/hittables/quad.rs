@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use super::AABB;
+use crate::{materials::Material, HitRecord, Hittable, Point, Vec3};
+
+/// Thickness given to the bounding box of flat primitives.
+pub(crate) const FLAT_PADDING: f64 = 0.0001;
+
+/// Parallelogram spanned by the edges `u` and `v` starting at the corner `q`.
+pub struct Quad {
+    q: Point,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    d: f64,
+    w: Vec3,
+    material: Arc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(q: Point, u: Vec3, v: Vec3, material: Arc<dyn Material>) -> Self {
+        let n = u.cross(v);
+        let normal = n.unit_vector();
+        let d = normal.dot(q);
+        let w = n / n.dot(n);
+
+        Self {
+            q,
+            u,
+            v,
+            normal,
+            d,
+            w,
+            material,
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &crate::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let denom = self.normal.dot(ray.direction());
+        if float_eq_cero!(denom, 1e-8) {
+            return false;
+        }
+
+        let t = (self.d - self.normal.dot(ray.origin())) / denom;
+        if t < t_min || t > t_max {
+            return false;
+        }
+
+        let p = ray.at(t);
+        let planar = p - self.q;
+        let alpha = self.w.dot(planar.cross(self.v));
+        let beta = self.w.dot(self.u.cross(planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return false;
+        }
+
+        rec.t = t;
+        rec.p = p;
+        rec.u = alpha;
+        rec.v = beta;
+        rec.set_face_normal(ray, self.normal);
+        rec.material = Arc::clone(&self.material);
+
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        *output_box = AABB::from_points(&[
+            self.q,
+            self.q + self.u,
+            self.q + self.v,
+            self.q + self.u + self.v,
+        ])
+        .pad(FLAT_PADDING);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color, Ray};
+
+    fn two_by_four() -> Quad {
+        Quad::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.0, 4.0),
+            Arc::new(Lambertian::new(Color::ones())),
+        )
+    }
+
+    #[test]
+    fn hit_uv() {
+        let quad = two_by_four();
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(0.5, 3.0, 3.0), Vec3::new(0.0, -1.0, 0.0));
+
+        assert!(quad.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 3.0, 1e-12));
+        assert!(float_eq!(rec.u, 0.25, 1e-12));
+        assert!(float_eq!(rec.v, 0.75, 1e-12));
+        assert!(rec.front_face == (quad.normal().y() > 0.0));
+    }
+
+    #[test]
+    fn miss_outside() {
+        let quad = two_by_four();
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(2.5, 3.0, 3.0), Vec3::new(0.0, -1.0, 0.0));
+
+        assert!(!quad.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+    }
+
+    #[test]
+    fn padded_box() {
+        let quad = two_by_four();
+        let mut bbox = AABB::new(Point::ceros(), Point::ceros());
+
+        assert!(quad.bounding_box(&mut bbox));
+        assert!(bbox.max.y() - bbox.min.y() > 0.0);
+        assert!(bbox.hit(
+            &Ray::new(Point::new(1.0, 1.0, 1.0), Vec3::new(0.0, -1.0, 0.0)),
+            0.0,
+            f64::INFINITY
+        ));
+    }
+}
@@ -0,0 +1,141 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::AABB;
+use crate::{materials::Material, onb::Onb, roots, HitRecord, Point, Ray, Vec3};
+
+/// Surface of revolution `x^2 + y^2 = a z^2 + b z + c` around the local `z`
+/// axis, cut at `0 <= z <= height` and optionally closed by flat caps.
+///
+/// This is the shared intersection code behind `Cylinder`, `Cone`,
+/// `Paraboloid` and `Hyperboloid`. The local frame has its origin at `base`
+/// and `z` pointing along `axis`.
+pub(crate) struct Quadric {
+    base: Point,
+    frame: Onb,
+    a: f64,
+    b: f64,
+    c: f64,
+    height: f64,
+    bottom_cap: bool,
+    top_cap: bool,
+    material: Arc<dyn Material>,
+}
+
+impl Quadric {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        base: Point,
+        axis: Vec3,
+        coefficients: (f64, f64, f64),
+        height: f64,
+        bottom_cap: bool,
+        top_cap: bool,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let (a, b, c) = coefficients;
+        Self {
+            base,
+            frame: Onb::from_w(axis),
+            a,
+            b,
+            c,
+            height,
+            bottom_cap,
+            top_cap,
+            material,
+        }
+    }
+
+    fn radius2_at(&self, z: f64) -> f64 {
+        (self.a * z * z + self.b * z + self.c).max(0.0)
+    }
+
+    fn angle_uv(p: Vec3) -> f64 {
+        (p.y().atan2(p.x()) + PI) / (2.0 * PI)
+    }
+
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let o = self.frame.to_local(ray.origin() - self.base);
+        let d = self.frame.to_local(ray.direction());
+
+        let qa = d.x() * d.x() + d.y() * d.y() - self.a * d.z() * d.z();
+        let qb = 2.0 * (o.x() * d.x() + o.y() * d.y() - self.a * o.z() * d.z()) - self.b * d.z();
+        let qc = o.x() * o.x() + o.y() * o.y() - self.a * o.z() * o.z() - self.b * o.z() - self.c;
+
+        let mut closest = t_max;
+        let mut hit = None;
+
+        for t in roots::solve_quadratic(qa, qb, qc) {
+            if t < t_min || t > closest {
+                continue;
+            }
+            let p = o + d * t;
+            if p.z() < 0.0 || p.z() > self.height {
+                continue;
+            }
+            let normal = Vec3::new(2.0 * p.x(), 2.0 * p.y(), -2.0 * self.a * p.z() - self.b);
+            let uv = (Quadric::angle_uv(p), p.z() / self.height);
+            closest = t;
+            hit = Some((t, normal, uv));
+        }
+
+        let caps = [
+            (self.bottom_cap, 0.0, -1.0),
+            (self.top_cap, self.height, 1.0),
+        ];
+        for &(enabled, z, facing) in caps.iter() {
+            if !enabled || float_eq_cero!(d.z()) {
+                continue;
+            }
+            let t = (z - o.z()) / d.z();
+            if t < t_min || t > closest {
+                continue;
+            }
+            let p = o + d * t;
+            let r2 = self.radius2_at(z);
+            let dist2 = p.x() * p.x() + p.y() * p.y();
+            if dist2 > r2 || float_eq_cero!(r2) {
+                continue;
+            }
+            let uv = (Quadric::angle_uv(p), (dist2 / r2).sqrt());
+            closest = t;
+            hit = Some((t, Vec3::new(0.0, 0.0, facing), uv));
+        }
+
+        match hit {
+            Some((t, normal, (u, v))) => {
+                rec.t = t;
+                rec.p = ray.at(t);
+                rec.u = u;
+                rec.v = v;
+                rec.set_face_normal(ray, self.frame.to_world(normal).unit_vector());
+                rec.material = Arc::clone(&self.material);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        // The widest point is at one of the ends, or at the vertex of the
+        // profile when it bulges out, like a hyperboloid with narrow ends.
+        let mut r2 = self.radius2_at(0.0).max(self.radius2_at(self.height));
+        if self.a < 0.0 {
+            let vertex = (-self.b / (2.0 * self.a)).clamp(0.0, self.height);
+            r2 = r2.max(self.radius2_at(vertex));
+        }
+        let r = r2.sqrt();
+
+        let mut corners = Vec::with_capacity(8);
+        for &x in [-r, r].iter() {
+            for &y in [-r, r].iter() {
+                for &z in [0.0, self.height].iter() {
+                    corners.push(self.base + self.frame.to_world(Vec3::new(x, y, z)));
+                }
+            }
+        }
+        *output_box = AABB::from_points(&corners);
+
+        true
+    }
+}
@@ -17,6 +17,18 @@ impl Sphere {
             material,
         }
     }
+
+    /// Maps a point on the unit sphere to `(u, v)`, with `u` going around the
+    /// Y axis starting at -X and `v` going from -Y to +Y.
+    pub fn get_uv(p: Point) -> (f64, f64) {
+        let theta = (-p.y()).acos();
+        let phi = (-p.z()).atan2(p.x()) + std::f64::consts::PI;
+
+        (
+            phi / (2.0 * std::f64::consts::PI),
+            theta / std::f64::consts::PI,
+        )
+    }
 }
 
 impl Hittable for Sphere {
@@ -45,6 +57,9 @@ impl Hittable for Sphere {
         rec.p = ray.at(rec.t);
         let outward_normal = (rec.p - self.center) / self.radius;
         rec.set_face_normal(ray, outward_normal);
+        let (u, v) = Sphere::get_uv(outward_normal);
+        rec.u = u;
+        rec.v = v;
         rec.material = Arc::clone(&self.material);
 
         true
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use super::AABB;
+use crate::{HitRecord, Hittable, Ray};
+
+/// Gives the hits of another object the IDs written to the object and
+/// material ID passes. Objects that aren't tagged get 0, like the sky.
+pub struct Tagged {
+    object: Arc<dyn Hittable>,
+    object_id: u32,
+    material_id: u32,
+}
+
+impl Tagged {
+    pub fn new(object: Arc<dyn Hittable>, object_id: u32, material_id: u32) -> Self {
+        Self {
+            object,
+            object_id,
+            material_id,
+        }
+    }
+}
+
+impl Hittable for Tagged {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        if !self.object.hit(ray, t_min, t_max, rec) {
+            return false;
+        }
+
+        rec.object_id = self.object_id;
+        rec.material_id = self.material_id;
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        self.object.bounding_box(output_box)
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64, hits: &mut Vec<HitRecord>) {
+        let start = hits.len();
+        self.object.hit_all(ray, t_min, t_max, hits);
+        for rec in hits[start..].iter_mut() {
+            rec.object_id = self.object_id;
+            rec.material_id = self.material_id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittables::{HittableList, Sphere, BVH},
+        materials::Lambertian,
+        Color, Point, Vec3,
+    };
+
+    #[test]
+    fn ids_of_closest_hit() {
+        let material = Arc::new(Lambertian::new(Color::ones()));
+        let sphere = |z| Arc::new(Sphere::new(Point::new(0.0, 0.0, z), 0.5, material.clone()));
+        let mut list = HittableList::new();
+        list.add(Arc::new(Tagged::new(sphere(-4.0), 7, 2)));
+        // Closer, but untagged
+        list.add(sphere(-2.0));
+        list.add(Arc::new(Tagged::new(sphere(-6.0), 9, 3)));
+
+        let ray = Ray::new(Point::ceros(), Vec3::new(0.0, 0.0, -1.0));
+        let mut rec = HitRecord::new();
+        assert!(list.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!((rec.object_id, rec.material_id), (0, 0));
+
+        let far = Ray::new(Point::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, -1.0));
+        let bvh = BVH::from_hittable_list(&mut list).unwrap();
+        assert!(bvh.hit(&far, 0.001, f64::INFINITY, &mut rec));
+        assert_eq!((rec.object_id, rec.material_id), (7, 2));
+        assert!(bvh.hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 1.5, 1e-9));
+        assert_eq!((rec.object_id, rec.material_id), (0, 0));
+    }
+}
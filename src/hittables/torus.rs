@@ -0,0 +1,152 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::AABB;
+use crate::{materials::Material, onb::Onb, roots, HitRecord, Hittable, Point, Vec3};
+
+/// Torus around `center` with its hole along `axis`. `major_radius` goes
+/// from the center to the middle of the tube and `minor_radius` is the
+/// radius of the tube itself.
+pub struct Torus {
+    center: Point,
+    frame: Onb,
+    major_radius: f64,
+    minor_radius: f64,
+    material: Arc<dyn Material>,
+}
+
+impl Torus {
+    pub fn new(
+        center: Point,
+        axis: Vec3,
+        major_radius: f64,
+        minor_radius: f64,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            center,
+            frame: Onb::from_w(axis),
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &crate::Ray, t_min: f64, t_max: f64, rec: &mut HitRecord) -> bool {
+        let d = self.frame.to_local(ray.direction());
+        let mut o = self.frame.to_local(ray.origin() - self.center);
+
+        // The quartic loses a lot of precision when the origin is far away,
+        // so the ray is first moved up to the bounding sphere.
+        let bound = self.major_radius + self.minor_radius;
+        let d_len2 = d.len2();
+        let half_b = o.dot(d);
+        let disc = half_b * half_b - d_len2 * (o.len2() - bound * bound);
+        if disc < 0.0 {
+            return false;
+        }
+        let t_enter = ((-half_b - disc.sqrt()) / d_len2).max(0.0);
+        o += d * t_enter;
+
+        let r2 = self.major_radius * self.major_radius;
+        let k = o.len2() - r2 - self.minor_radius * self.minor_radius;
+        let od = o.dot(d);
+
+        // (|o + t d|^2 + R^2 - r^2)^2 = 4 R^2 ((o + t d)_x^2 + (o + t d)_y^2)
+        let a = d_len2 * d_len2;
+        let b = 4.0 * d_len2 * od;
+        let c = 2.0 * d_len2 * k + 4.0 * od * od + 4.0 * r2 * d.z() * d.z();
+        let dd = 4.0 * k * od + 8.0 * r2 * o.z() * d.z();
+        let e = k * k - 4.0 * r2 * (self.minor_radius * self.minor_radius - o.z() * o.z());
+
+        let t = match roots::solve_quartic(a, b, c, dd, e)
+            .into_iter()
+            .map(|t| t + t_enter)
+            .find(|&t| t >= t_min && t <= t_max)
+        {
+            Some(t) => t,
+            None => return false,
+        };
+
+        let p = self.frame.to_local(ray.at(t) - self.center);
+        let ring = (p.x() * p.x() + p.y() * p.y()).sqrt();
+        let to_tube = if float_eq_cero!(ring) {
+            Vec3::ceros()
+        } else {
+            Vec3::new(p.x(), p.y(), 0.0) * (self.major_radius / ring)
+        };
+        let outward_normal = self.frame.to_world((p - to_tube) / self.minor_radius);
+
+        rec.t = t;
+        rec.p = ray.at(t);
+        rec.u = (p.y().atan2(p.x()) + PI) / (2.0 * PI);
+        rec.v = (p.z().atan2(ring - self.major_radius) + PI) / (2.0 * PI);
+        rec.set_face_normal(ray, outward_normal.unit_vector());
+        rec.material = Arc::clone(&self.material);
+
+        true
+    }
+
+    fn bounding_box(&self, output_box: &mut AABB) -> bool {
+        let r = self.major_radius + self.minor_radius;
+        let h = self.minor_radius;
+
+        let mut corners = Vec::with_capacity(8);
+        for &x in [-r, r].iter() {
+            for &y in [-r, r].iter() {
+                for &z in [-h, h].iter() {
+                    corners.push(self.center + self.frame.to_world(Vec3::new(x, y, z)));
+                }
+            }
+        }
+        *output_box = AABB::from_points(&corners);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{materials::Lambertian, Color, Ray};
+
+    fn torus() -> Torus {
+        Torus::new(
+            Point::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            2.0,
+            0.5,
+            Arc::new(Lambertian::new(Color::ones())),
+        )
+    }
+
+    #[test]
+    fn through_the_tube() {
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(100.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+
+        assert!(torus().hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 97.5, 1e-6));
+        assert!(rec.normal.approx_eq_epsilon(Vec3::new(1.0, 0.0, 0.0), 1e-6));
+
+        // Second crossing is the inner side of the same tube
+        let t = rec.t;
+        assert!(torus().hit(&ray, t + 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 98.5, 1e-6));
+        assert!(!rec.front_face);
+    }
+
+    #[test]
+    fn through_the_hole() {
+        let mut rec = HitRecord::new();
+        let ray = Ray::new(Point::new(0.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+
+        assert!(!torus().hit(&ray, 0.001, f64::INFINITY, &mut rec));
+
+        let ray = Ray::new(Point::new(2.0, 10.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(torus().hit(&ray, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 9.5, 1e-6));
+        assert!(rec.normal.approx_eq_epsilon(Vec3::new(0.0, 1.0, 0.0), 1e-6));
+    }
+}
@@ -0,0 +1,172 @@
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use crate::{image_io, Color, Error, Result};
+
+/// Rendered image in linear (HDR) color, row by row from the top.
+#[derive(Clone)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    /// Fails if there isn't one pixel for each of `width * height`.
+    pub fn new(width: u32, height: u32, pixels: Vec<Color>) -> Result<Self> {
+        if (width as usize).checked_mul(height as usize) != Some(pixels.len()) {
+            return Err(Error::InvalidConfig(format!(
+                "A {}x{} image can't have {} pixels",
+                width,
+                height,
+                pixels.len()
+            )));
+        }
+        Ok(Self::from_pixels(width, height, pixels))
+    }
+
+    /// Same as `new`, for pixels that are known to fit, like the ones made
+    /// from another image or film of that size.
+    pub(crate) fn from_pixels(width: u32, height: u32, pixels: Vec<Color>) -> Self {
+        debug_assert_eq!(pixels.len(), width as usize * height as usize);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[(y * self.width + x) as usize]
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// Gamma corrected 8 bit RGB values, as written to PNG files.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|c| vec![c.r(1), c.g(1), c.b(1)])
+            .collect()
+    }
+
+    /// Reads a PNG, HDR or PFM file, going by its extension. PNG values
+    /// are taken as gamma corrected like the ones `to_rgb8` gives.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let open = || File::open(path).map(BufReader::new);
+        let (width, height, pixels) = match extension.as_str() {
+            "png" => {
+                let (width, height, pixels) = image_io::read_png(open()?)?;
+                (width, height, pixels.into_iter().map(|c| c * c).collect())
+            }
+            "hdr" => image_io::read_hdr(open()?)?,
+            "pfm" => image_io::read_pfm(open()?)?,
+            _ => {
+                return Err(Error::InvalidConfig(format!(
+                    "Can't read images like {}, only PNG, HDR and PFM",
+                    path.display()
+                )))
+            }
+        };
+        match (u32::try_from(width), u32::try_from(height)) {
+            (Ok(width), Ok(height)) => Self::new(width, height, pixels),
+            _ => Err(Error::Parse(format!(
+                "{} is too big, {}x{}",
+                path.display(),
+                width,
+                height
+            ))),
+        }
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        image_io::write_png(file, self.width, self.height, &self.to_rgb8())?;
+        Ok(())
+    }
+
+    /// Saves the linear colors as a Radiance HDR file.
+    pub fn save_hdr<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        image_io::write_hdr(file, self.width, self.height, &self.pixels)?;
+        Ok(())
+    }
+
+    /// Saves the colors as a PFM file, which also keeps negative values.
+    pub fn save_pfm<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        image_io::write_pfm(file, self.width, self.height, &self.pixels)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pixels_must_fit() {
+        assert!(Image::new(2, 2, vec![Color::ones(); 3]).is_err());
+        assert!(Image::new(u32::MAX, u32::MAX, Vec::new()).is_err());
+        assert!(Image::new(0, 5, Vec::new()).is_ok());
+    }
+
+    #[test]
+    fn rgb8_is_gamma_corrected() {
+        let image = Image::new(2, 1, vec![Color::ones(), Color::ones() * 0.25]).unwrap();
+        assert!(image.pixel(1, 0).approx_eq(Color::ones() * 0.25));
+        assert_eq!(image.to_rgb8(), vec![255, 255, 255, 127, 127, 127]);
+    }
+
+    #[test]
+    fn load_saved() {
+        let image =
+            Image::new(2, 1, vec![Color::new(0.25, 1.0, 0.0), Color::ones() * 4.0]).unwrap();
+        let dir = std::env::temp_dir();
+        let path = |ext| dir.join(format!("ray_tracing_load_saved.{}", ext));
+        image.save_png(path("png")).unwrap();
+        image.save_hdr(path("hdr")).unwrap();
+        image.save_pfm(path("pfm")).unwrap();
+
+        let png = Image::load(path("png")).unwrap();
+        assert!(png
+            .pixel(0, 0)
+            .approx_eq_epsilon(Color::new(0.25, 1.0, 0.0), 0.01));
+        assert!(png.pixel(1, 0).approx_eq(Color::ones()));
+        for ext in ["hdr", "pfm"] {
+            let loaded = Image::load(path(ext)).unwrap();
+            assert!(loaded.pixel(1, 0).approx_eq(Color::ones() * 4.0));
+            std::fs::remove_file(path(ext)).unwrap();
+        }
+        std::fs::remove_file(path("png")).unwrap();
+        assert!(Image::load(path("jpg")).is_err());
+    }
+
+    #[test]
+    fn load_bad_header() {
+        let path = std::env::temp_dir().join("ray_tracing_load_bad_header.pfm");
+        std::fs::write(&path, b"PF\n0 4\n-1.0\n").unwrap();
+        assert!(matches!(Image::load(&path), Err(Error::Parse(_))));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    fs::File,
+    io::{self, BufRead, Read, Write},
+    path::Path,
+};
+
+use crate::{Color, Error, Result};
+
+/// Largest image the HDR and PFM readers accept, so a bad header can't ask
+/// for all the memory.
+const MAX_PIXELS: usize = 1 << 28;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn parse_error(msg: &str) -> Error {
+    Error::Parse(msg.to_string())
+}
+
+/// Number of pixels of a `width` by `height` image read from a header.
+fn pixel_count(width: usize, height: usize) -> Result<usize> {
+    match width.checked_mul(height) {
+        Some(pixels) if pixels > 0 && pixels <= MAX_PIXELS => Ok(pixels),
+        _ => Err(Error::Parse(format!(
+            "Can't read a {}x{} image",
+            width, height
+        ))),
+    }
+}
+
+/// Reads a PNG as values in `[0, 1]`, returning the width, the height and
+/// the colors row by row from the top. 16 bit images keep their full
+/// precision, grayscale images have the same value in every channel and
+/// alpha is ignored. The values aren't converted to linear color.
+pub fn read_png<R: Read>(r: R) -> io::Result<(usize, usize, Vec<Color>)> {
+    let mut decoder = png::Decoder::new(r);
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let (info, mut reader) = decoder.read_info()?;
+    let mut buf = vec![0u8; info.buffer_size()];
+    reader.next_frame(&mut buf)?;
+
+    let channels = match info.color_type {
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::RGB => 3,
+        png::ColorType::RGBA => 4,
+        png::ColorType::Indexed => {
+            return Err(invalid_data(
+                "Indexed PNGs should have been expanded while decoding",
+            ))
+        }
+    };
+    let bytes_per_sample = match info.bit_depth {
+        png::BitDepth::Sixteen => 2,
+        _ => 1,
+    };
+    let sample = |bytes: &[u8]| -> f64 {
+        if bytes_per_sample == 2 {
+            u16::from_be_bytes([bytes[0], bytes[1]]) as f64 / u16::MAX as f64
+        } else {
+            bytes[0] as f64 / u8::MAX as f64
+        }
+    };
+
+    let (width, height) = (info.width as usize, info.height as usize);
+    let mut colors = Vec::with_capacity(width * height);
+    for row in buf.chunks(info.line_size).take(height) {
+        for pixel in row.chunks(channels * bytes_per_sample).take(width) {
+            let value = |channel| sample(&pixel[channel * bytes_per_sample..]);
+            // Grayscale+alpha and RGBA have the alpha channel last
+            colors.push(if channels < 3 {
+                Color::ones() * value(0)
+            } else {
+                Color::new(value(0), value(1), value(2))
+            });
+        }
+    }
+
+    Ok((width, height, colors))
+}
+
+/// Reads a PNG as one value in `[0, 1]` per pixel, returning the width, the
+/// height and the values row by row from the top. Color images use the
+/// average of their channels, see `read_png`.
+pub fn read_grayscale_png<P: AsRef<Path>>(path: P) -> io::Result<(usize, usize, Vec<f64>)> {
+    let (width, height, colors) = read_png(File::open(path)?)?;
+    let values = colors.iter().map(|c| (c[0] + c[1] + c[2]) / 3.0).collect();
+    Ok((width, height, values))
+}
+
+/// Reads a line of a header, without the newline.
+fn read_line<R: BufRead>(r: &mut R) -> io::Result<String> {
+    let mut line = String::new();
+    if r.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "The header ended too soon",
+        ));
+    }
+    Ok(line.trim_end().to_string())
+}
+
+/// Reads a Radiance HDR image like the ones `write_hdr` writes, run length
+/// encoded or not, as linear colors row by row from the top.
+pub fn read_hdr<R: BufRead>(mut r: R) -> Result<(usize, usize, Vec<Color>)> {
+    if !read_line(&mut r)?.starts_with("#?") {
+        return Err(parse_error("Not a Radiance HDR image"));
+    }
+    loop {
+        let line = read_line(&mut r)?;
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with("FORMAT=") && line != "FORMAT=32-bit_rle_rgbe" {
+            return Err(parse_error("Only RGBE HDR images can be read"));
+        }
+    }
+    let size = read_line(&mut r)?;
+    let (height, width) = match size.split_whitespace().collect::<Vec<_>>()[..] {
+        ["-Y", height, "+X", width] => (height.parse::<usize>(), width.parse::<usize>()),
+        _ => return Err(parse_error("Only top to bottom HDR images can be read")),
+    };
+    let (height, width) = match (height, width) {
+        (Ok(height), Ok(width)) => (height, width),
+        _ => return Err(parse_error("Bad HDR image size")),
+    };
+    pixel_count(width, height)?;
+
+    // Grows as the rows come, in case the file is shorter than it says
+    let mut colors = Vec::new();
+    let mut rgbe = vec![[0u8; 4]; width];
+    for _ in 0..height {
+        read_scanline(&mut r, &mut rgbe)?;
+        colors.extend(rgbe.iter().map(|&[r, g, b, e]| {
+            if e == 0 {
+                Color::ceros()
+            } else {
+                let scale = 2f64.powi(e as i32 - 128 - 8);
+                Color::new(r as f64, g as f64, b as f64) * scale
+            }
+        }));
+    }
+    Ok((width, height, colors))
+}
+
+/// Reads a row of RGBE pixels, flat or with the run length encoding of
+/// Radiance, where each of the four channels is encoded on its own.
+fn read_scanline<R: Read>(r: &mut R, rgbe: &mut [[u8; 4]]) -> io::Result<()> {
+    let mut start = [0u8; 4];
+    r.read_exact(&mut start)?;
+    let width = rgbe.len();
+    let encoded =
+        start[0] == 2 && start[1] == 2 && start[2] & 0x80 == 0 && (8..0x8000).contains(&width);
+    if !encoded {
+        rgbe[0] = start;
+        for pixel in rgbe[1..].iter_mut() {
+            r.read_exact(pixel)?;
+        }
+        return Ok(());
+    }
+    if ((start[2] as usize) << 8 | start[3] as usize) != width {
+        return Err(invalid_data("HDR scanline of the wrong width"));
+    }
+
+    for channel in 0..4 {
+        let mut x = 0;
+        while x < width {
+            let mut count = [0u8; 2];
+            r.read_exact(&mut count[..1])?;
+            // Above 128 it's a run of one value, else that many values
+            let (run, n) = if count[0] > 128 {
+                (true, count[0] as usize - 128)
+            } else {
+                (false, count[0] as usize)
+            };
+            if n == 0 || x + n > width {
+                return Err(invalid_data("Bad run length in HDR scanline"));
+            }
+            for (i, pixel) in rgbe[x..x + n].iter_mut().enumerate() {
+                if !run || i == 0 {
+                    r.read_exact(&mut count[1..])?;
+                }
+                pixel[channel] = count[1];
+            }
+            x += n;
+        }
+    }
+    Ok(())
+}
+
+/// Reads a PFM image, color or grayscale and of either endianness, as
+/// colors row by row from the top.
+pub fn read_pfm<R: BufRead>(mut r: R) -> Result<(usize, usize, Vec<Color>)> {
+    let channels = match read_line(&mut r)?.as_str() {
+        "PF" => 3,
+        "Pf" => 1,
+        _ => return Err(parse_error("Not a PFM image")),
+    };
+    let size = read_line(&mut r)?;
+    let size: Vec<usize> = size
+        .split_whitespace()
+        .map(|n| n.parse().map_err(|_| parse_error("Bad PFM image size")))
+        .collect::<Result<_>>()?;
+    let (width, height) = match size[..] {
+        [width, height] => (width, height),
+        _ => return Err(parse_error("Bad PFM image size")),
+    };
+    let scale: f64 = read_line(&mut r)?
+        .parse()
+        .map_err(|_| parse_error("Bad PFM scale"))?;
+
+    // Read as it comes instead of allocated up front, so a short file with
+    // a big size fails without taking that much memory
+    let bytes = pixel_count(width, height)? * channels * 4;
+    let mut data = Vec::new();
+    r.take(bytes as u64).read_to_end(&mut data)?;
+    if data.len() != bytes {
+        return Err(parse_error("The PFM image ended too soon"));
+    }
+    let floats: Vec<f64> = data
+        .chunks(4)
+        .map(|b| {
+            let b = [b[0], b[1], b[2], b[3]];
+            if scale < 0.0 {
+                f32::from_le_bytes(b) as f64
+            } else {
+                f32::from_be_bytes(b) as f64
+            }
+        })
+        .collect();
+
+    // PFM rows go from the bottom up
+    let colors = floats
+        .chunks(width * channels)
+        .rev()
+        .flat_map(|row| row.chunks(channels))
+        .map(|c| {
+            if channels == 1 {
+                Color::ones() * c[0]
+            } else {
+                Color::new(c[0], c[1], c[2])
+            }
+        })
+        .collect();
+    Ok((width, height, colors))
+}
+
+/// Writes 8 bit RGB `data` as a PNG.
+pub fn write_png<W: Write>(w: W, width: u32, height: u32, data: &[u8]) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(w, width, height);
+    encoder.set_color(png::ColorType::RGB);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Best);
+
+    let to_io = io::Error::other;
+    let mut writer = encoder.write_header().map_err(to_io)?;
+    writer.write_image_data(data).map_err(to_io)
+}
+
+/// Writes linear colors, row by row from the top, as a Radiance HDR image
+/// without run length encoding.
+pub fn write_hdr<W: Write>(mut w: W, width: u32, height: u32, pixels: &[Color]) -> io::Result<()> {
+    write!(
+        w,
+        "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+        height, width
+    )?;
+
+    for c in pixels {
+        let max = c[0].max(c[1]).max(c[2]);
+        let rgbe = if max < 1e-32 {
+            [0; 4]
+        } else {
+            // Shared exponent so the largest channel's mantissa is in [0.5, 1)
+            let exponent = max.log2().floor() as i32 + 1;
+            let scale = 256.0 / 2f64.powi(exponent);
+            [
+                (c[0].max(0.0) * scale) as u8,
+                (c[1].max(0.0) * scale) as u8,
+                (c[2].max(0.0) * scale) as u8,
+                (exponent + 128) as u8,
+            ]
+        };
+        w.write_all(&rgbe)?;
+    }
+    Ok(())
+}
+
+/// Writes colors, row by row from the top, as a little endian PFM image.
+/// Unlike HDR it keeps negative values and full float precision.
+pub fn write_pfm<W: Write>(mut w: W, width: u32, height: u32, pixels: &[Color]) -> io::Result<()> {
+    // A negative scale means little endian
+    write!(w, "PF\n{} {}\n-1.0\n", width, height)?;
+
+    // PFM rows go from the bottom up
+    for row in pixels.chunks(width as usize).rev() {
+        for c in row {
+            for a in 0..3 {
+                w.write_all(&(c[a] as f32).to_le_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hdr_shared_exponent() {
+        let mut hdr = Vec::new();
+        write_hdr(
+            &mut hdr,
+            2,
+            1,
+            &[Color::new(1.0, 0.5, 0.25), Color::ceros()],
+        )
+        .unwrap();
+
+        let header = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n";
+        assert_eq!(&hdr[..header.len()], header);
+        assert_eq!(&hdr[header.len()..], &[128, 64, 32, 129, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn pfm_bottom_up() {
+        let mut pfm = Vec::new();
+        write_pfm(&mut pfm, 1, 2, &[Color::new(-1.0, 0.0, 0.0), Color::ones()]).unwrap();
+
+        let header = b"PF\n1 2\n-1.0\n";
+        assert_eq!(&pfm[..header.len()], header);
+        let floats: Vec<f32> = pfm[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        assert_eq!(floats, vec![1.0, 1.0, 1.0, -1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn read_what_was_written() {
+        let colors = [Color::new(-1.0, 0.5, 2.0), Color::ones(), Color::ceros()];
+        let mut pfm = Vec::new();
+        write_pfm(&mut pfm, 1, 3, &colors).unwrap();
+        let (width, height, read) = read_pfm(&pfm[..]).unwrap();
+        assert_eq!((width, height), (1, 3));
+        for (a, &b) in read.iter().zip(&colors) {
+            assert!(a.approx_eq(b));
+        }
+
+        // HDR can't keep negative values
+        let colors = [Color::new(0.25, 0.5, 2.0), Color::ones(), Color::ceros()];
+        let mut hdr = Vec::new();
+        write_hdr(&mut hdr, 3, 1, &colors).unwrap();
+        let (width, height, read) = read_hdr(&hdr[..]).unwrap();
+        assert_eq!((width, height), (3, 1));
+        for (a, &b) in read.iter().zip(&colors) {
+            assert!(a.approx_eq(b));
+        }
+
+        let mut png = Vec::new();
+        write_png(&mut png, 2, 1, &[255, 0, 51, 0, 0, 0]).unwrap();
+        let (_, _, read) = read_png(&png[..]).unwrap();
+        assert!(read[0].approx_eq(Color::new(1.0, 0.0, 0.2)));
+    }
+
+    #[test]
+    fn run_length_encoded_hdr() {
+        let mut hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 8\n".to_vec();
+        hdr.extend([2, 2, 0, 8]);
+        // A run of 8 for red, then 8 values of green, and runs of 0s
+        hdr.extend([136, 128, 8, 0, 64, 0, 64, 0, 64, 0, 64, 136, 0, 136, 129]);
+        let (_, _, read) = read_hdr(&hdr[..]).unwrap();
+        assert!(read[0].approx_eq(Color::new(1.0, 0.0, 0.0)));
+        assert!(read[1].approx_eq(Color::new(1.0, 0.5, 0.0)));
+    }
+
+    #[test]
+    fn bad_sizes() {
+        for header in [
+            &b"PF\n0 4\n-1.0\n"[..],
+            b"PF\n4 0\n-1.0\n",
+            b"PF\n18446744073709551615 2\n-1.0\n",
+            b"PF\n100000 100000\n-1.0\n",
+            b"Pf\n1000 1000\n-1.0\n",
+        ] {
+            assert!(
+                matches!(read_pfm(header), Err(Error::Parse(_))),
+                "{}",
+                String::from_utf8_lossy(header)
+            );
+        }
+
+        let hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 0\n";
+        assert!(matches!(read_hdr(&hdr[..]), Err(Error::Parse(_))));
+        let hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 4294967296 +X 4294967296\n";
+        assert!(matches!(read_hdr(&hdr[..]), Err(Error::Parse(_))));
+    }
+}
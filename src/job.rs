@@ -0,0 +1,260 @@
+use std::{convert::TryFrom, fmt::Write};
+
+use crate::{
+    cameras::{ApertureShape, PhysicalCamera},
+    CameraConfig, Error, ImgConfig, Projection, Result, RunConfig, SceneConfig, Vec3,
+};
+
+/// Largest image a job can ask for, so a bad job can't take all the memory.
+const MAX_PIXELS: u32 = 1 << 26;
+
+/// Everything a worker needs to render tiles of the same image as the
+/// coordinator, sent as `key value` lines.
+pub(crate) struct Job {
+    pub img_config: ImgConfig,
+    pub cam_config: CameraConfig,
+    pub scene_config: SceneConfig,
+    pub use_bvh: bool,
+}
+
+/// Text for the job of rendering `config`, with the scene generated from
+/// `scene_seed`. Only perspective cameras with a plain circular aperture
+/// can be sent, and adaptive sampling, AOVs, denoising and post-processing
+/// aren't supported.
+pub(crate) fn job_text(config: &RunConfig, scene_seed: u64) -> Result<String> {
+    let RunConfig {
+        img_config,
+        cam_config,
+        scene_config,
+        use_bvh,
+        ..
+    } = config;
+    let lens = &cam_config.lens;
+    let plain_lens = matches!(lens.aperture_shape, ApertureShape::Circle)
+        && lens.cat_eye == 0.0
+        && lens.shift == (0.0, 0.0)
+        && lens.tilt == (0.0, 0.0);
+    if cam_config.projection != Projection::Perspective || !plain_lens {
+        return Err(Error::InvalidConfig(
+            "Only perspective cameras without lens effects can be distributed".to_string(),
+        ));
+    }
+    if img_config.adaptive.is_some() {
+        return Err(Error::InvalidConfig(
+            "Adaptive sampling can't be distributed".to_string(),
+        ));
+    }
+    if !img_config.aovs.is_empty() || img_config.denoise.is_some() || !img_config.post.is_empty() {
+        return Err(Error::InvalidConfig(
+            "AOVs, denoising and post-processing can't be distributed".to_string(),
+        ));
+    }
+
+    let v = |v: Vec3| format!("{} {} {}", v.x(), v.y(), v.z());
+    let mut text = String::new();
+    // Writing to a String can't fail
+    let mut line = |key: &str, value: String| writeln!(text, "{} {}", key, value).unwrap();
+
+    line("width", img_config.width.to_string());
+    line("aspect_ratio", img_config.aspect_ratio.to_string());
+    line(
+        "samples_per_pixel",
+        img_config.samples_per_pixel.to_string(),
+    );
+    line("max_depth", img_config.max_depth.to_string());
+    line("tile_size", img_config.tile_size.to_string());
+    line("seed", img_config.seed.to_string());
+    line("lookfrom", v(cam_config.lookfrom));
+    line("lookat", v(cam_config.lookat));
+    line("vec_up", v(cam_config.vec_up));
+    line("vert_fov", cam_config.vert_fov.to_string());
+    line("aperture", cam_config.aperture.to_string());
+    line("focus_dist", cam_config.focus_dist.to_string());
+    if let Some(p) = cam_config.physical {
+        line(
+            "physical",
+            format!(
+                "{} {} {} {} {} {} {}",
+                p.f_number,
+                p.shutter_speed,
+                p.iso,
+                p.focal_length,
+                p.sensor.0,
+                p.sensor.1,
+                p.units_per_mm
+            ),
+        );
+    }
+    if let Some((s, t)) = cam_config.autofocus {
+        line("autofocus", format!("{} {}", s, t));
+    }
+    line(
+        "small_sphere_count",
+        scene_config.small_sphere_count.to_string(),
+    );
+    line("diffuse_prob", scene_config.diffuse_prob.to_string());
+    line("metal_prob", scene_config.metal_prob.to_string());
+    line("scene_seed", scene_seed.to_string());
+    line("use_bvh", use_bvh.to_string());
+
+    Ok(text)
+}
+
+impl Job {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut job = Job {
+            img_config: ImgConfig::default(),
+            cam_config: CameraConfig::default(),
+            scene_config: SceneConfig::default(),
+            use_bvh: true,
+        };
+
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let mut words = line.split_whitespace();
+            let key = words.next().unwrap_or_default();
+            let values: Vec<&str> = words.collect();
+
+            let value = |i: usize| -> Result<&str> {
+                values
+                    .get(i)
+                    .copied()
+                    .ok_or_else(|| Error::Parse(format!("Missing value for {}", key)))
+            };
+            let num = |i: usize| -> Result<f64> {
+                value(i)?
+                    .parse()
+                    .map_err(|_| Error::Parse(format!("Bad number for {}", key)))
+            };
+            let int = |i: usize| -> Result<u64> {
+                value(i)?
+                    .parse()
+                    .map_err(|_| Error::Parse(format!("Bad integer for {}", key)))
+            };
+            let small = |i: usize| -> Result<u32> {
+                u32::try_from(int(i)?).map_err(|_| Error::Parse(format!("{} is too large", key)))
+            };
+            let vec = || -> Result<Vec3> { Ok(Vec3::new(num(0)?, num(1)?, num(2)?)) };
+
+            let img = &mut job.img_config;
+            let cam = &mut job.cam_config;
+            let scene = &mut job.scene_config;
+            match key {
+                "width" => img.width = small(0)?,
+                "aspect_ratio" => img.aspect_ratio = num(0)?,
+                "samples_per_pixel" => img.samples_per_pixel = small(0)?,
+                "max_depth" => {
+                    img.max_depth = i32::try_from(small(0)?)
+                        .map_err(|_| Error::Parse(format!("{} is too large", key)))?
+                }
+                "tile_size" => img.tile_size = small(0)?,
+                "seed" => img.seed = int(0)?,
+                "lookfrom" => cam.lookfrom = vec()?,
+                "lookat" => cam.lookat = vec()?,
+                "vec_up" => cam.vec_up = vec()?,
+                "vert_fov" => cam.vert_fov = num(0)?,
+                "aperture" => cam.aperture = num(0)?,
+                "focus_dist" => cam.focus_dist = num(0)?,
+                "physical" => {
+                    cam.physical = Some(PhysicalCamera {
+                        f_number: num(0)?,
+                        shutter_speed: num(1)?,
+                        iso: num(2)?,
+                        focal_length: num(3)?,
+                        sensor: (num(4)?, num(5)?),
+                        units_per_mm: num(6)?,
+                    })
+                }
+                "autofocus" => cam.autofocus = Some((num(0)?, num(1)?)),
+                "small_sphere_count" => scene.small_sphere_count = small(0)?,
+                "diffuse_prob" => scene.diffuse_prob = num(0)?,
+                "metal_prob" => scene.metal_prob = num(0)?,
+                "scene_seed" => scene.seed = Some(int(0)?),
+                "use_bvh" => job.use_bvh = value(0)? == "true",
+                _ => return Err(Error::Parse(format!("Unknown job setting {}", key))),
+            }
+        }
+
+        job.check()?;
+        Ok(job)
+    }
+
+    /// Makes sure the image can be rendered, before anything is allocated
+    /// for it.
+    fn check(&self) -> Result<()> {
+        let img = &self.img_config;
+        let invalid = |msg: &str| Err(Error::InvalidConfig(msg.to_string()));
+        if !(img.aspect_ratio.is_finite() && img.aspect_ratio > 0.0) {
+            return invalid("The aspect ratio must be positive");
+        }
+        match img.width.checked_mul(img.height()) {
+            Some(pixels) if pixels > 0 && pixels <= MAX_PIXELS => {}
+            _ => {
+                return Err(Error::InvalidConfig(format!(
+                    "Can't render a {}x{} image",
+                    img.width,
+                    img.height()
+                )))
+            }
+        }
+        if img.samples_per_pixel == 0 {
+            return invalid("There must be at least one sample per pixel");
+        }
+        if img.tile_size == 0 {
+            return invalid("The tile size can't be 0");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Point;
+
+    #[test]
+    fn round_trip() {
+        let mut config = RunConfig::default();
+        config.img_config.width = 321;
+        config.img_config.aspect_ratio = 4.0 / 3.0;
+        config.cam_config.lookfrom = Point::new(1.5, -2.0, 0.1);
+        config.cam_config.autofocus = Some((0.25, 0.5));
+        config.cam_config.physical = Some(PhysicalCamera::default());
+        config.scene_config.metal_prob = 0.3;
+
+        let job = Job::parse(&job_text(&config, 77).unwrap()).unwrap();
+        assert_eq!(job.img_config.width, 321);
+        assert_eq!(job.img_config.aspect_ratio, 4.0 / 3.0);
+        assert!(job
+            .cam_config
+            .lookfrom
+            .approx_eq(Point::new(1.5, -2.0, 0.1)));
+        assert_eq!(job.cam_config.autofocus, Some((0.25, 0.5)));
+        assert_eq!(job.cam_config.physical, Some(PhysicalCamera::default()));
+        assert_eq!(job.scene_config.metal_prob, 0.3);
+        assert_eq!(job.scene_config.seed, Some(77));
+        assert!(job.use_bvh);
+    }
+
+    #[test]
+    fn rejects_images_it_cant_render() {
+        for text in [
+            "width 100000\naspect_ratio 0.001",
+            "width 4294967296",
+            "width 0",
+            "aspect_ratio 0",
+            "aspect_ratio NaN",
+            "samples_per_pixel 0",
+            "tile_size 0",
+        ] {
+            assert!(Job::parse(text).is_err(), "{}", text);
+        }
+        assert!(Job::parse("width 64\naspect_ratio 2").is_ok());
+    }
+
+    #[test]
+    fn only_plain_perspective() {
+        let mut config = RunConfig::default();
+        config.cam_config.projection = Projection::Equirectangular;
+        assert!(job_text(&config, 0).is_err());
+    }
+}
@@ -1,198 +1,528 @@
 use rand::Rng;
-use rayon::prelude::*;
-use std::{fs::File, io::BufWriter, sync::Arc};
+use std::{
+    fs::File,
+    io::{self, BufWriter},
+    path::Path,
+    sync::Arc,
+    time::Instant,
+};
 
 #[macro_use]
 mod macros;
 
-mod camera;
+pub mod animation;
+pub mod cameras;
+mod compare;
 mod config;
+pub mod distributed;
+mod error;
+#[cfg(test)]
+mod golden;
 pub mod hittables;
+mod image;
+mod image_io;
+mod job;
 pub mod materials;
+mod onb;
+mod random;
 mod ray;
+pub mod render;
+mod roots;
+pub mod server;
 mod vec3;
 
-pub use camera::Camera;
-pub use config::{CameraConfig, ImgConfig, RunConfig, SceneConfig};
+pub use cameras::{Camera, Projection};
+pub use compare::{compare, Comparison, Metric};
+pub use config::{
+    AdaptiveConfig, AnimationConfig, CameraConfig, CheckpointConfig, DenoiseConfig, ImgConfig,
+    PostEffect, ProgressiveConfig, RunConfig, SceneConfig,
+};
+pub use error::{Error, Result};
 pub use hittables::Hittable;
-use hittables::{HitRecord, HittableList, Sphere, BVH};
+use hittables::{HitRecord, HittableList, Sphere, Tagged, AABB, BVH};
+pub use image::Image;
 pub use materials::Material;
 use materials::{Dielectric, Lambertian, Metal};
 pub use ray::Ray;
+use render::{new_film, render_frame, render_pass, shot_seed, Checkpoint, Film, Monitor, Shot};
+pub use render::{Aov, CancelToken, Progress, TileOrder};
 pub use vec3::{Color, Point, Vec3};
 
+/// Cameras built per frame for the motion blur of an animated camera.
+const CAMERA_MOTION_STEPS: usize = 8;
+
 pub fn ray_color(ray: &Ray, world: &dyn Hittable, depth: i32) -> Vec3 {
+    trace(ray, world, depth, &mut 0)
+}
+
+/// `ray_color` that also counts the rays it traces in `rays`.
+pub(crate) fn trace(ray: &Ray, world: &dyn Hittable, depth: i32, rays: &mut u64) -> Vec3 {
     if depth <= 0 {
         return Color::ceros();
     }
 
+    *rays += 1;
     let mut rec = HitRecord::new();
-    if world.hit(ray, 0.001, std::f64::INFINITY, &mut rec) {
-        let mut scattered = Ray::new(Point::ceros(), Vec3::ceros());
-        let mut attenuation = Vec3::ceros();
+    if world.hit(ray, 0.001, f64::INFINITY, &mut rec) {
+        return shade(ray, &mut rec, world, depth, rays);
+    }
+    sky(ray)
+}
 
-        if Arc::clone(&rec.material).scatter(ray, &mut rec, &mut attenuation, &mut scattered) {
-            return attenuation * ray_color(&scattered, world, depth - 1);
-        }
-        return Color::ceros();
+/// Light leaving the hit in `rec` back along `ray`, `depth` being what was
+/// left for `ray`.
+pub(crate) fn shade(
+    ray: &Ray,
+    rec: &mut HitRecord,
+    world: &dyn Hittable,
+    depth: i32,
+    rays: &mut u64,
+) -> Color {
+    let mut scattered = Ray::new(Point::ceros(), Vec3::ceros());
+    let mut attenuation = Vec3::ceros();
+
+    if Arc::clone(&rec.material).scatter(ray, rec, &mut attenuation, &mut scattered) {
+        return attenuation * trace(&scattered, world, depth - 1, rays);
     }
+    Color::ceros()
+}
 
+/// Light coming from the sky along `ray`, the only light in the scenes.
+pub(crate) fn sky(ray: &Ray) -> Color {
     let unit_dir = ray.direction().unit_vector();
     let t = 0.5 * (unit_dir.y() + 1.0);
     Vec3::ones() * (1.0 - t) + Vec3::new(0.5, 0.7, 1.0) * t
 }
 
-pub fn write_to_file(x: u32, y: u32, data: &[u8], filename: &str) {
-    let file = File::create(filename).expect("Failed creating file");
-    let ref mut w = BufWriter::new(file);
-
-    let mut encoder = png::Encoder::new(w, x, y);
-    encoder.set_color(png::ColorType::RGB);
-    encoder.set_depth(png::BitDepth::Eight);
-    encoder.set_compression(png::Compression::Best);
-
-    let mut writer = encoder.write_header().expect("Couldn't create writer");
-    writer
-        .write_image_data(data)
-        .expect("Error while saving image data");
+pub fn write_to_file(x: u32, y: u32, data: &[u8], filename: &str) -> Result<()> {
+    let file = File::create(filename)?;
+    image_io::write_png(BufWriter::new(file), x, y, data)?;
+    Ok(())
 }
 
-pub fn random_scene(config: &SceneConfig) -> HittableList {
-    config.validate();
+/// Material IDs of the spheres in `random_scene`, by kind.
+const DIFFUSE_ID: u32 = 1;
+const METAL_ID: u32 = 2;
+const GLASS_ID: u32 = 3;
+
+/// Ground and big spheres with small random ones around them. Objects are
+/// tagged with their position in the list, starting at 1, and materials
+/// with their kind.
+pub fn random_scene(config: &SceneConfig) -> Result<HittableList> {
+    config.validate()?;
     let mut world = HittableList::new();
+    let add = |world: &mut HittableList, sphere: Sphere, material_id| {
+        let object_id = world.count() as u32 + 1;
+        world.add(Arc::new(Tagged::new(
+            Arc::new(sphere),
+            object_id,
+            material_id,
+        )));
+    };
 
     let ground_material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
-    world.add(Arc::new(Sphere::new(
-        Point::new(0.0, -1000.0, 0.0),
-        1000.0,
-        ground_material,
-    )));
-
-    let mut rng = rand::thread_rng();
-    let goal_count = config.small_sphere_count as f64;
-    let mut current_count = 0.0;
-    let mut iterations_remainig = 484.0;
-    for a in -11..11 {
-        let a = a as f64;
-        for b in -11..11 {
-            let b = b as f64;
-
-            let keep_prob = ((goal_count - current_count) / iterations_remainig).min(1.0);
-            iterations_remainig -= 1.0;
-            if float_eq!(current_count, goal_count) {
-                break;
-            }
-            if !rng.gen_bool(keep_prob) {
-                continue;
-            }
+    add(
+        &mut world,
+        Sphere::new(Point::new(0.0, -1000.0, 0.0), 1000.0, ground_material),
+        DIFFUSE_ID,
+    );
 
-            let choose_mat: f64 = rng.gen();
-            let center = Point::new(a + 0.9 * rng.gen::<f64>(), 0.2, b + 0.9 * rng.gen::<f64>());
-
-            if (center - Point::new(4.0, 0.2, 0.0)).len() > 0.9 {
-                let sphere_material: Arc<dyn Material>;
-
-                if choose_mat < config.diffuse_prob {
-                    // diffuse
-                    let albedo = Color::random_in_unit_cube() * Color::random_in_unit_cube();
-                    sphere_material = Arc::new(Lambertian::new(albedo));
-                } else if choose_mat < config.diffuse_prob + config.metal_prob {
-                    // metal
-                    let albedo = Color::random_in_range(0.5, 1.0);
-                    let fuzz = rng.gen_range(0.0..0.5);
-                    sphere_material = Arc::new(Metal::new(albedo, fuzz));
-                } else {
-                    // glass
-                    sphere_material = Arc::new(Dielectric::new(1.5));
+    let mut small_spheres = || {
+        let goal_count = config.small_sphere_count as f64;
+        let mut current_count = 0.0;
+        let mut iterations_remainig = 484.0;
+        for a in -11..11 {
+            let a = a as f64;
+            for b in -11..11 {
+                let b = b as f64;
+
+                let keep_prob = ((goal_count - current_count) / iterations_remainig).min(1.0);
+                iterations_remainig -= 1.0;
+                if float_eq!(current_count, goal_count) {
+                    break;
+                }
+                if !random::with_rng(|rng| rng.gen_bool(keep_prob)) {
+                    continue;
                 }
 
-                world.add(Arc::new(Sphere::new(center, 0.2, sphere_material)));
-                current_count += 1.0;
+                let choose_mat: f64 = random::random();
+                let center = Point::new(
+                    a + 0.9 * random::random::<f64>(),
+                    0.2,
+                    b + 0.9 * random::random::<f64>(),
+                );
+
+                if (center - Point::new(4.0, 0.2, 0.0)).len() > 0.9 {
+                    let sphere_material: Arc<dyn Material>;
+                    let material_id;
+
+                    if choose_mat < config.diffuse_prob {
+                        // diffuse
+                        let albedo = Color::random_in_unit_cube() * Color::random_in_unit_cube();
+                        sphere_material = Arc::new(Lambertian::new(albedo));
+                        material_id = DIFFUSE_ID;
+                    } else if choose_mat < config.diffuse_prob + config.metal_prob {
+                        // metal
+                        let albedo = Color::random_in_range(0.5, 1.0);
+                        let fuzz = random::with_rng(|rng| rng.gen_range(0.0..0.5));
+                        sphere_material = Arc::new(Metal::new(albedo, fuzz));
+                        material_id = METAL_ID;
+                    } else {
+                        // glass
+                        sphere_material = Arc::new(Dielectric::new(1.5));
+                        material_id = GLASS_ID;
+                    }
+
+                    add(
+                        &mut world,
+                        Sphere::new(center, 0.2, sphere_material),
+                        material_id,
+                    );
+                    current_count += 1.0;
+                }
             }
         }
+    };
+    // A seed makes them repeatable without touching the thread's numbers
+    match config.seed {
+        Some(seed) => random::with_seed(seed, small_spheres),
+        None => small_spheres(),
     }
 
     let material1 = Arc::new(Dielectric::new(1.5));
-    world.add(Arc::new(Sphere::new(
-        Point::new(0.0, 1.0, 0.0),
-        1.0,
-        material1,
-    )));
+    add(
+        &mut world,
+        Sphere::new(Point::new(0.0, 1.0, 0.0), 1.0, material1),
+        GLASS_ID,
+    );
     let material2 = Arc::new(Lambertian::new(Color::new(0.4, 0.2, 0.1)));
-    world.add(Arc::new(Sphere::new(
-        Point::new(-4.0, 1.0, 0.0),
-        1.0,
-        material2,
-    )));
+    add(
+        &mut world,
+        Sphere::new(Point::new(-4.0, 1.0, 0.0), 1.0, material2),
+        DIFFUSE_ID,
+    );
     let material3 = Arc::new(Metal::new(Color::new(0.7, 0.6, 0.5), 0.0));
-    world.add(Arc::new(Sphere::new(
-        Point::new(4.0, 1.0, 0.0),
-        1.0,
-        material3,
-    )));
+    add(
+        &mut world,
+        Sphere::new(Point::new(4.0, 1.0, 0.0), 1.0, material3),
+        METAL_ID,
+    );
+
+    Ok(world)
+}
 
-    world
+/// Puts the objects of `scene` in a `BVH` if asked to. Objects without a
+/// bounding box, like planes, stay next to it.
+pub(crate) fn build_world(scene: HittableList, use_bvh: bool) -> Result<Arc<dyn Hittable>> {
+    if !use_bvh {
+        return Ok(Arc::new(scene));
+    }
+
+    let (mut bounded, unbounded): (Vec<_>, Vec<_>) = scene
+        .objects
+        .into_iter()
+        .partition(|o| o.bounding_box(&mut AABB::new(Point::ceros(), Point::ceros())));
+    let mut world = HittableList::with_objects(unbounded);
+    if !bounded.is_empty() {
+        world.add(Arc::new(BVH::new(&mut bounded)?));
+    }
+    Ok(Arc::new(world))
 }
 
-pub fn run(config: &RunConfig) {
+/// Renders the image and returns its film. A cancelled render returns the
+/// samples taken until then, and pixels of tiles that weren't started have
+/// none.
+pub fn render_film(config: &RunConfig) -> Result<Film> {
+    let RunConfig {
+        img_config,
+        cam_config,
+        scene_config,
+        use_bvh,
+        progress,
+        cancel,
+        ..
+    } = config;
+
+    let world = build_world(random_scene(scene_config)?, *use_bvh)?;
+    let cameras = [cam_config.camera(img_config.aspect_ratio, &*world, 0.0)?];
+    let shot = Shot {
+        world: &world,
+        cameras: &cameras,
+        views: &[cam_config.view()],
+        shutter: (0.0, 0.0),
+        exposure: cam_config.exposure(),
+    };
+    let monitor = Monitor::new(*progress, cancel.clone());
+    let seed = shot_seed(img_config.seed, 0, 0);
+    render_frame(img_config, &shot, seed, &monitor)
+}
+
+/// Renders the image, denoised and post-processed as `img_config` says.
+/// See `render_film`.
+pub fn render(config: &RunConfig) -> Result<Image> {
+    let film = render_film(config)?;
+    to_image(&film, &config.img_config)
+}
+
+fn to_image(film: &Film, img_config: &ImgConfig) -> Result<Image> {
+    let image = match &img_config.denoise {
+        Some(denoise) => render::denoise(film, denoise)?,
+        None => film.to_image(),
+    };
+    if img_config.post.is_empty() {
+        return Ok(image);
+    }
+    render::post_process(&image, &img_config.post)
+}
+
+/// Renders the image to `config.filename`, and the samples taken for each
+/// pixel to the file adaptive sampling asks for, if any. Each of the AOVs
+/// goes to a PFM file named after the image, like `res.depth.pfm`.
+pub fn run(config: &RunConfig) -> Result<()> {
+    save_film(&render_film(config)?, config)
+}
+
+/// Writes `film` to `config.filename` the way `run` does: denoised and
+/// post-processed, with the AOVs and the adaptive sample counts next to it.
+pub fn save_film(film: &Film, config: &RunConfig) -> Result<()> {
+    let img_config = &config.img_config;
+    let image = to_image(film, img_config)?;
+    write_to_file(
+        image.width(),
+        image.height(),
+        &image.to_rgb8(),
+        config.filename,
+    )?;
+
+    for aov in img_config.aovs.iter() {
+        let path = Path::new(config.filename).with_extension(format!("{}.pfm", aov.name()));
+        if let Some(image) = film.aov(*aov) {
+            image.save_pfm(path)?;
+        }
+    }
+
+    if let Some(adaptive) = &img_config.adaptive {
+        if let Some(samples_filename) = &adaptive.samples_filename {
+            let samples = film.samples_to_rgb8(adaptive.max_samples);
+            write_to_file(film.width(), film.height(), &samples, samples_filename)?;
+        }
+    }
+    Ok(())
+}
+
+/// Renders in passes over the whole image until one of the limits in
+/// `progressive` is reached or it is cancelled, and returns the accumulated
+/// film. Passes are only cut short by cancelling, so the time limit can be
+/// overrun by up to one pass. `filename` is only written every
+/// `write_interval`, saving the result is up to the caller.
+///
+/// With a checkpoint configured, the state is saved between passes, and a
+/// resumed render gives the same image it would have without stopping.
+pub fn run_progressive(config: &RunConfig, progressive: &ProgressiveConfig) -> Result<Film> {
     let RunConfig {
         img_config,
         cam_config,
         scene_config,
         filename,
-        quiet,
         use_bvh,
+        progress,
+        cancel,
     } = config;
+    if progressive.samples_per_pass == 0 {
+        return Err(Error::InvalidConfig(
+            "Passes need at least one sample".to_string(),
+        ));
+    }
 
-    let img_height: u32 = (img_config.width as f64 / img_config.aspect_ratio) as u32;
+    let resumed = match progressive.checkpoint.as_ref().filter(|c| c.resume) {
+        Some(checkpoint) => {
+            match Checkpoint::load(checkpoint.path, img_config.width, img_config.height()) {
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                loaded => Some(loaded?),
+            }
+        }
+        None => None,
+    };
 
-    let camera = Camera::new(
-        cam_config.lookfrom,
-        cam_config.lookat,
-        cam_config.vec_up,
-        cam_config.vert_fov,
-        img_config.aspect_ratio,
-        cam_config.aperture,
-        cam_config.focus_dist,
-    );
+    // The scene has to be the same one to continue the render
+    let scene_seed = resumed
+        .as_ref()
+        .map(|(resumed, _)| resumed.scene_seed)
+        .or(scene_config.seed)
+        .unwrap_or_else(rand::random);
+    let scene = random_scene(&SceneConfig {
+        seed: Some(scene_seed),
+        ..*scene_config
+    })?;
+    let world = build_world(scene, *use_bvh)?;
+    let cameras = [cam_config.camera(img_config.aspect_ratio, &*world, 0.0)?];
+    let shot = Shot {
+        world: &world,
+        cameras: &cameras,
+        views: &[cam_config.view()],
+        shutter: (0.0, 0.0),
+        exposure: cam_config.exposure(),
+    };
+    let monitor = Monitor::new(*progress, cancel.clone());
 
-    let mut scene = random_scene(&scene_config);
-    let world: Arc<dyn Hittable> = if *use_bvh {
-        Arc::new(HittableList::with_objects(vec![Arc::new(
-            BVH::from_hittable_list(&mut scene),
-        )]))
+    let no_limits = progressive.time_limit.is_none()
+        && progressive.sample_limit.is_none()
+        && progressive.error_threshold.is_none();
+    let sample_limit = if no_limits {
+        Some(img_config.samples_per_pixel)
     } else {
-        Arc::new(scene)
+        progressive.sample_limit
     };
 
-    let mut buff = vec![0u8; (img_config.width * img_height * 3) as usize];
-
-    buff.par_chunks_mut(img_config.width as usize * 3)
-        .rev()
-        .enumerate()
-        .for_each(|(j, row)| {
-            row.par_chunks_mut(3).enumerate().for_each_init(
-                || (rand::thread_rng(), world.clone()),
-                |(rng, world), (i, pixel)| {
-                    let mut pixel_color = Color::ceros();
-
-                    for _ in 0..img_config.samples_per_pixel {
-                        let u = (i as f64 + rng.gen::<f64>()) / (img_config.width - 1) as f64;
-                        let v = (j as f64 + rng.gen::<f64>()) / (img_height - 1) as f64;
-                        let ray = camera.get_ray(u, v);
-                        pixel_color += ray_color(&ray, &**world, img_config.max_depth);
-
-                        pixel[0] = pixel_color.r(img_config.samples_per_pixel);
-                        pixel[1] = pixel_color.g(img_config.samples_per_pixel);
-                        pixel[2] = pixel_color.b(img_config.samples_per_pixel);
-                    }
-                },
-            )
-        });
+    let start = Instant::now();
+    let mut last_write = start;
+    let mut last_save = start;
+    let (mut film, mut samples, mut pass) = match resumed {
+        Some((resumed, film)) => (film, resumed.samples, resumed.passes),
+        None => (
+            new_film(img_config, img_config.width, img_config.height()),
+            0,
+            0,
+        ),
+    };
+    let mut cancelled = false;
+
+    loop {
+        let done = sample_limit.is_some_and(|limit| samples >= limit)
+            || progressive
+                .time_limit
+                .is_some_and(|limit| start.elapsed() >= limit)
+            || progressive
+                .error_threshold
+                .is_some_and(|threshold| film.error() <= threshold);
+        if done || monitor.is_cancelled() {
+            break;
+        }
 
-    if !quiet {
-        write_to_file(img_config.width, img_height, &buff, filename);
+        let pass_samples = match sample_limit {
+            Some(limit) => progressive.samples_per_pass.min(limit - samples),
+            None => progressive.samples_per_pass,
+        };
+        render_pass(
+            img_config,
+            &shot,
+            &|_, _| pass_samples,
+            shot_seed(img_config.seed, 0, pass),
+            &mut film,
+            &monitor,
+        )?;
+        if monitor.is_cancelled() {
+            // A partial pass can't be resumed, so it isn't checkpointed
+            cancelled = true;
+            break;
+        }
+        samples += pass_samples;
+        pass += 1;
+
+        if let Some(interval) = progressive.write_interval {
+            if last_write.elapsed() >= interval {
+                let image = to_image(&film, img_config)?;
+                write_to_file(image.width(), image.height(), &image.to_rgb8(), filename)?;
+                last_write = Instant::now();
+            }
+        }
+
+        if let Some(checkpoint) = &progressive.checkpoint {
+            if last_save.elapsed() >= checkpoint.interval {
+                save_checkpoint(checkpoint.path, pass, samples, scene_seed, &film)?;
+                last_save = Instant::now();
+            }
+        }
     }
+
+    if let Some(checkpoint) = progressive.checkpoint.as_ref().filter(|_| !cancelled) {
+        save_checkpoint(checkpoint.path, pass, samples, scene_seed, &film)?;
+    }
+    Ok(film)
+}
+
+fn save_checkpoint(
+    path: &str,
+    passes: u64,
+    samples: u32,
+    scene_seed: u64,
+    film: &Film,
+) -> Result<()> {
+    let checkpoint = Checkpoint {
+        passes,
+        samples,
+        scene_seed,
+    };
+    checkpoint.save(path, film)?;
+    Ok(())
+}
+
+/// Renders the frames of `animation` to numbered files. The scene is built
+/// once for all of them: animated objects are placed by the time of each
+/// ray, so only the camera changes between frames. Once cancelled, no more
+/// frames are started, and the one being rendered isn't written.
+pub fn run_animation(config: &RunConfig, animation: &AnimationConfig) -> Result<()> {
+    let RunConfig {
+        img_config,
+        cam_config,
+        scene_config,
+        use_bvh,
+        progress,
+        cancel,
+        ..
+    } = config;
+    let monitor = Monitor::new(*progress, cancel.clone());
+
+    let img_height = img_config.height();
+
+    let mut scene = random_scene(scene_config)?;
+    for object in animation.objects.iter() {
+        scene.add(object.clone());
+    }
+    let world = build_world(scene, *use_bvh)?;
+
+    let parts = if animation.camera.is_static() || animation.shutter <= 0.0 {
+        1
+    } else {
+        CAMERA_MOTION_STEPS
+    };
+
+    for frame in animation.frames.clone() {
+        if monitor.is_cancelled() {
+            break;
+        }
+        let open = frame as f64 / animation.fps;
+        let close = open + animation.shutter / animation.fps;
+
+        let mut cameras: Vec<Arc<dyn Camera>> = Vec::with_capacity(parts);
+        let mut views = Vec::with_capacity(parts);
+        for part in 0..parts {
+            let time = open + (close - open) * (part as f64 + 0.5) / parts as f64;
+            let config = animation.camera.apply(cam_config, time);
+            cameras.push(config.camera(img_config.aspect_ratio, &*world, time)?);
+            views.push(config.view());
+        }
+        let shot = Shot {
+            world: &world,
+            cameras: &cameras,
+            views: &views,
+            shutter: (open, close),
+            exposure: cam_config.exposure(),
+        };
+        let film = render_frame(
+            img_config,
+            &shot,
+            shot_seed(img_config.seed, frame as u64, 0),
+            &monitor,
+        )?;
+
+        if !monitor.is_cancelled() {
+            let filename = format!("{}{:04}.png", animation.prefix, frame);
+            let image = to_image(&film, img_config)?;
+            write_to_file(img_config.width, img_height, &image.to_rgb8(), &filename)?;
+        }
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -202,7 +532,7 @@ mod tests {
     fn run_scene_count(config: &SceneConfig) {
         let mut values = Vec::new();
         for _ in 0..100 {
-            let world = random_scene(config);
+            let world = random_scene(config).unwrap();
             values.push(world.count() - 4);
         }
         let avg = (values.iter().sum::<usize>() as f64 / values.len() as f64) as i64;
@@ -222,4 +552,151 @@ mod tests {
             run_scene_count(&conf);
         }
     }
+
+    #[test]
+    fn progressive_sample_limit() {
+        let mut config = RunConfig::default();
+        config.img_config.width = 16;
+        config.img_config.max_depth = 4;
+        config.scene_config.small_sphere_count = 0;
+        let progressive = ProgressiveConfig {
+            sample_limit: Some(6),
+            ..Default::default()
+        };
+
+        let film = run_progressive(&config, &progressive).unwrap();
+        assert_eq!(film.samples(0, 0), 6);
+        assert_eq!(film.samples(15, 8), 6);
+    }
+
+    #[test]
+    fn resume_matches_uninterrupted() {
+        let mut config = RunConfig::default();
+        config.img_config.width = 16;
+        config.img_config.max_depth = 4;
+        config.scene_config.small_sphere_count = 10;
+        config.scene_config.seed = Some(3);
+        let path = std::env::temp_dir().join("ray_tracing_resume.checkpoint");
+        let path = path.to_str().unwrap();
+        let progressive = |samples, resume| ProgressiveConfig {
+            sample_limit: Some(samples),
+            samples_per_pass: 2,
+            checkpoint: Some(CheckpointConfig {
+                path,
+                interval: std::time::Duration::from_secs(0),
+                resume,
+            }),
+            ..Default::default()
+        };
+
+        let whole = run_progressive(&config, &progressive(8, false)).unwrap();
+        run_progressive(&config, &progressive(4, false)).unwrap();
+        let resumed = run_progressive(&config, &progressive(8, true)).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(resumed.samples(3, 3), 8);
+        assert_eq!(whole.to_rgb8(), resumed.to_rgb8());
+        for y in 0..whole.height() {
+            for x in 0..whole.width() {
+                for a in 0..3 {
+                    assert_eq!(whole.pixel(x, y)[a], resumed.pixel(x, y)[a]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn progress_counts_tiles() {
+        use std::sync::Mutex;
+
+        let reports = Mutex::new(Vec::new());
+        let progress = |p: &Progress| reports.lock().unwrap().push(*p);
+        let mut config = RunConfig {
+            progress: Some(&progress),
+            ..Default::default()
+        };
+        config.img_config.width = 16;
+        config.img_config.samples_per_pixel = 2;
+        config.img_config.max_depth = 4;
+        config.img_config.tile_size = 4;
+        config.scene_config.small_sphere_count = 0;
+
+        let image = render(&config).unwrap();
+        assert_eq!((image.width(), image.height()), (16, 9));
+        let reports = reports.into_inner().unwrap();
+        // 16x9 in tiles of 4 is 4 by 3 tiles
+        assert_eq!(reports.len(), 12);
+        let last = reports.iter().max_by_key(|p| p.tiles_done).unwrap();
+        assert_eq!((last.tiles_done, last.tiles_total), (12, 12));
+        assert_eq!((last.pixels_done, last.pixels_total), (144, 144));
+        assert!(last.rays_per_sec > 0.0);
+        assert_eq!(last.eta, std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn cancel_returns_partial_film() {
+        let cancel = CancelToken::new();
+        let progress = {
+            let cancel = cancel.clone();
+            move |_: &Progress| cancel.cancel()
+        };
+        let mut config = RunConfig {
+            progress: Some(&progress),
+            cancel: Some(cancel),
+            ..Default::default()
+        };
+        config.img_config.width = 64;
+        config.img_config.samples_per_pixel = 2;
+        config.img_config.max_depth = 4;
+        config.img_config.tile_size = 4;
+        config.scene_config.small_sphere_count = 0;
+
+        let film = render_film(&config).unwrap();
+        let rendered = (0..film.height())
+            .flat_map(|y| (0..film.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| film.samples(x, y) > 0)
+            .count();
+        // Each thread finishes at most the tile it had started
+        assert!(rendered > 0);
+        assert!(rendered <= 16 * rayon::current_num_threads());
+        assert!(rendered < (film.width() * film.height()) as usize);
+    }
+
+    #[test]
+    fn bad_input_is_an_error() {
+        let mut config = RunConfig::default();
+        config.scene_config.diffuse_prob = 1.5;
+        config.scene_config.metal_prob = -0.5;
+        assert!(matches!(render(&config), Err(Error::InvalidConfig(_))));
+
+        let path = std::env::temp_dir().join("ray_tracing_missing_dir/res.png");
+        let written = write_to_file(1, 1, &[0, 0, 0], path.to_str().unwrap());
+        assert!(matches!(written, Err(Error::Io(_))));
+        assert!(matches!(BVH::new(&mut []), Err(Error::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn planes_stay_out_of_the_bvh() {
+        let material: Arc<dyn materials::Material> = Arc::new(Lambertian::new(Color::ones()));
+        let mut scene = HittableList::new();
+        scene.add(Arc::new(hittables::Plane::new(
+            Point::ceros(),
+            Vec3::new(0.0, 1.0, 0.0),
+            material.clone(),
+        )));
+        scene.add(Arc::new(Sphere::new(
+            Point::new(0.0, 1.0, 0.0),
+            0.5,
+            material,
+        )));
+
+        let world = build_world(scene, true).unwrap();
+        let mut rec = HitRecord::new();
+        let down = Ray::new(Point::new(0.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(world.hit(&down, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 3.5, 1e-9));
+        let beside = Ray::new(Point::new(3.0, 5.0, 0.0), Vec3::new(0.0, -1.0, 0.0));
+        assert!(world.hit(&beside, 0.001, f64::INFINITY, &mut rec));
+        assert!(float_eq!(rec.t, 5.0, 1e-9));
+    }
 }
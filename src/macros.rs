@@ -1,6 +1,6 @@
 macro_rules! float_eq {
     ($lhs:expr, $rhs:expr) => {
-        float_eq!($lhs, $rhs, std::f64::EPSILON)
+        float_eq!($lhs, $rhs, f64::EPSILON)
     };
     ($lhs:expr, $rhs:expr, $epsilon:expr) => {
         ($lhs - $rhs).abs() < $epsilon
@@ -9,7 +9,7 @@ macro_rules! float_eq {
 
 macro_rules! float_eq_cero {
     ($lhs:expr) => {
-        float_eq_cero!($lhs, std::f64::EPSILON)
+        float_eq_cero!($lhs, f64::EPSILON)
     };
     ($lhs:expr, $epsilon:expr) => {
         $lhs.abs() < $epsilon
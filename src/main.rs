@@ -1,5 +1,121 @@
-use ray_tracing::{run, RunConfig};
+use std::{env, net::TcpListener, process, time::Duration};
+
+use ray_tracing::{
+    compare,
+    distributed::{run_coordinator, run_worker},
+    run_progressive, save_film,
+    server::serve,
+    write_to_file, CheckpointConfig, Error, Image, Metric, ProgressiveConfig, Result, RunConfig,
+};
 
 fn main() {
-    run(&RunConfig::default());
+    if let Err(e) = run() {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let args: Vec<String> = env::args().collect();
+    let config = RunConfig::default();
+    let address = || {
+        args.get(2)
+            .ok_or_else(|| Error::InvalidConfig(format!("Usage: {} <address>", args[1])))
+    };
+    match args.get(1).map(String::as_str) {
+        Some("compare") => return compare_images(&args[2..]),
+        // Spreads the render over the workers started with `worker <address>`
+        Some("coordinator") => {
+            let listener = TcpListener::bind(address()?)?;
+            let film = run_coordinator(&config, listener)?;
+            return write_to_file(
+                film.width(),
+                film.height(),
+                &film.to_rgb8(),
+                config.filename,
+            );
+        }
+        Some("worker") => return run_worker(address()?.as_str(), rayon::current_num_threads()),
+        Some("serve") => return serve(TcpListener::bind(address()?)?),
+        _ => {}
+    }
+
+    let value_of = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|i| args.get(i + 1))
+    };
+
+    // With `--checkpoint <file>` the render is saved as it goes, so it can
+    // be stopped at any time and continued with `--resume`
+    let checkpoint = value_of("--checkpoint");
+    let resume = args.iter().any(|arg| arg == "--resume");
+    if checkpoint.is_none() && !resume {
+        return ray_tracing::run(&config);
+    }
+    let progressive = ProgressiveConfig {
+        checkpoint: Some(CheckpointConfig {
+            path: checkpoint.map_or("res.checkpoint", String::as_str),
+            interval: Duration::from_secs(300),
+            resume,
+        }),
+        ..Default::default()
+    };
+
+    let film = run_progressive(&config, &progressive)?;
+    save_film(&film, &config)
+}
+
+/// `compare <reference> <image> [--metric <name>] [--threshold <value>]
+/// [--diff <file>]` prints how far the image is from the reference, writes
+/// the difference in false color to `--diff` and exits with code 2 when the
+/// metric, FLIP by default, isn't within the threshold.
+fn compare_images(args: &[String]) -> Result<()> {
+    let usage = || {
+        Error::InvalidConfig(
+            "Usage: compare <reference> <image> [--metric <name>] [--threshold <value>] \
+             [--diff <file>]"
+                .to_string(),
+        )
+    };
+    let value_of = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .map(|i| args.get(i + 1).ok_or_else(usage))
+            .transpose()
+    };
+    let (reference, image) = match args {
+        [reference, image, ..] if !reference.starts_with("--") && !image.starts_with("--") => {
+            (Image::load(reference)?, Image::load(image)?)
+        }
+        _ => return Err(usage()),
+    };
+    let metric: Metric = value_of("--metric")?.map_or(Ok(Metric::Flip), |m| m.parse())?;
+    let threshold = value_of("--threshold")?
+        .map(|t| {
+            t.parse::<f64>()
+                .map_err(|_| Error::Parse(format!("Bad threshold {}", t)))
+        })
+        .transpose()?;
+
+    let comparison = compare(&reference, &image)?;
+    for metric in Metric::ALL {
+        println!("{:>6}: {}", metric.name(), comparison.value(metric));
+    }
+    if let Some(path) = value_of("--diff")? {
+        comparison.difference.save_png(path)?;
+    }
+
+    if let Some(threshold) = threshold {
+        if !comparison.passes(metric, threshold) {
+            eprintln!(
+                "{} of {} isn't within {}",
+                metric.name(),
+                comparison.value(metric),
+                threshold
+            );
+            process::exit(2);
+        }
+    }
+    Ok(())
 }
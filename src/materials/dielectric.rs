@@ -1,5 +1,5 @@
 use super::Material;
-use crate::{Color, Ray, Vec3};
+use crate::{random, Color, Ray, Vec3};
 
 pub struct Dielectric {
     ri: f64,
@@ -37,17 +37,20 @@ impl Material for Dielectric {
         let cos_theta = rec.normal.dot(-unit_direction).min(1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
-        let direction;
-        if refraction_ratio * sin_theta > 1.0
-            || Dielectric::reflectance(cos_theta, refraction_ratio) > rand::random()
+        let direction = if refraction_ratio * sin_theta > 1.0
+            || Dielectric::reflectance(cos_theta, refraction_ratio) > random::random()
         {
-            direction = Vec3::reflect(unit_direction, rec.normal);
+            Vec3::reflect(unit_direction, rec.normal)
         } else {
-            direction = Vec3::refract(unit_direction, rec.normal, refraction_ratio);
-        }
+            Vec3::refract(unit_direction, rec.normal, refraction_ratio)
+        };
+
+        *scattered = Ray::with_time(rec.p, direction, ray.time());
 
-        *scattered = Ray::new(rec.p, direction);
+        true
+    }
 
+    fn is_specular(&self) -> bool {
         true
     }
 }
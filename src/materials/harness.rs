@@ -0,0 +1,319 @@
+//! Checks that materials are physically plausible, for any `Material`: the
+//! light `scatter` keeps is at most what arrives (a white furnace), `eval`
+//! and `pdf` agree with what `scatter` does, the BSDF is reciprocal, and a
+//! chi-square test compares the sampled directions with `pdf`.
+//!
+//! Everything happens at a point with the normal on `z`, for rays arriving
+//! from straight above to almost grazing.
+
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{Dielectric, Lambertian, Material, Metal};
+use crate::{random, Color, HitRecord, Point, Ray, Vec3};
+
+const SAMPLES: usize = 50_000;
+
+/// Cosines between the normal and the directions rays arrive from.
+const COS_WO: [f64; 4] = [1.0, 0.7, 0.3, 0.05];
+
+/// Chi-square bins, over `cos(theta)` and `phi` so they have the same area.
+const THETA_BINS: usize = 16;
+const PHI_BINS: usize = 32;
+
+/// Bins expected to get fewer samples than this are pooled.
+const MIN_EXPECTED: f64 = 5.0;
+
+/// Upper normal quantile of the chi-square test's significance, 0.001.
+const Z_SIGNIFICANCE: f64 = 3.09;
+
+fn hit() -> HitRecord {
+    HitRecord {
+        normal: Vec3::new(0.0, 0.0, 1.0),
+        front_face: true,
+        ..HitRecord::new()
+    }
+}
+
+fn wo(cos: f64) -> Vec3 {
+    Vec3::new((1.0 - cos * cos).sqrt(), 0.0, cos)
+}
+
+/// Direction of `cos_theta` and `phi` around the normal.
+fn direction(cos_theta: f64, phi: f64) -> Vec3 {
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+/// Attenuation and unit direction `scatter` gives for a ray from `wo`, or
+/// `None` if it absorbs it.
+fn scatter(material: &dyn Material, wo: Vec3) -> Option<(Color, Vec3)> {
+    let ray = Ray::new(wo, -wo);
+    let mut attenuation = Color::ceros();
+    let mut scattered = Ray::new(Point::ceros(), Vec3::ceros());
+    material
+        .scatter(&ray, &mut hit(), &mut attenuation, &mut scattered)
+        .then(|| (attenuation, scattered.direction().unit_vector()))
+}
+
+/// Midpoint rule integral of `f` over the directions with `cos_theta` and
+/// `phi` in the given ranges, split in `n` by `n` cells.
+fn integrate(
+    f: impl Fn(Vec3) -> Color,
+    (z0, z1): (f64, f64),
+    (phi0, phi1): (f64, f64),
+    n: usize,
+) -> Color {
+    let (dz, dphi) = ((z1 - z0) / n as f64, (phi1 - phi0) / n as f64);
+    let mut sum = Color::ceros();
+    for i in 0..n {
+        for j in 0..n {
+            sum += f(direction(
+                z0 + (i as f64 + 0.5) * dz,
+                phi0 + (j as f64 + 0.5) * dphi,
+            ));
+        }
+    }
+    sum * (dz * dphi)
+}
+
+/// Integral over the sphere, fine enough for lobes a few degrees wide.
+fn integrate_sphere(f: impl Fn(Vec3) -> Color) -> Color {
+    integrate(f, (-1.0, 1.0), (-PI, PI), 400)
+}
+
+/// Light `scatter` keeps on average for rays from `wo`, and the fraction of
+/// them it doesn't absorb.
+fn sampled_energy(material: &dyn Material, wo: Vec3) -> (Color, f64) {
+    random::reseed(1);
+    let (mut energy, mut scattered) = (Color::ceros(), 0);
+    for _ in 0..SAMPLES {
+        if let Some((attenuation, _)) = scatter(material, wo) {
+            energy += attenuation;
+            scattered += 1;
+        }
+    }
+    (energy / SAMPLES as f64, scattered as f64 / SAMPLES as f64)
+}
+
+/// White furnace: a white material can't give back more light than it
+/// gets.
+fn check_energy(material: &dyn Material) -> Vec<Color> {
+    COS_WO
+        .iter()
+        .map(|&cos| {
+            let (energy, _) = sampled_energy(material, wo(cos));
+            for a in 0..3 {
+                assert!(energy[a] <= 1.0, "{} at cos {}", energy, cos);
+            }
+            energy
+        })
+        .collect()
+}
+
+/// `eval` and `pdf` integrate to what `scatter` does, and give the
+/// attenuations it returns.
+fn check_eval_and_pdf(material: &dyn Material) {
+    let rec = hit();
+    for &cos in COS_WO.iter() {
+        let wo = wo(cos);
+        let (energy, scattered) = sampled_energy(material, wo);
+        let pdf_total = integrate_sphere(|wi| Color::ones() * material.pdf(&rec, wo, wi))[0];
+        // Up to the error of the integration at the edges of narrow lobes
+        assert!(pdf_total <= 1.005, "pdf integrates to {}", pdf_total);
+        assert!(
+            float_eq!(pdf_total, scattered, 0.01),
+            "pdf integrates to {} but {} scatters at cos {}",
+            pdf_total,
+            scattered,
+            cos
+        );
+        let reflected = integrate_sphere(|wi| material.eval(&rec, wo, wi) * wi.z().max(0.0));
+        assert!(
+            reflected.approx_eq_epsilon(energy, 0.01),
+            "eval integrates to {} but {} is kept at cos {}",
+            reflected,
+            energy,
+            cos
+        );
+
+        random::reseed(2);
+        for _ in 0..1000 {
+            if let Some((attenuation, wi)) = scatter(material, wo) {
+                let weight = material.eval(&rec, wo, wi) * wi.z() / material.pdf(&rec, wo, wi);
+                assert!(
+                    weight.approx_eq_epsilon(attenuation, 1e-6),
+                    "{} {}",
+                    weight,
+                    attenuation
+                );
+            }
+        }
+    }
+}
+
+/// `eval` stays the same swapping the directions.
+fn check_reciprocity(material: &dyn Material) {
+    let rec = hit();
+    random::reseed(3);
+    for _ in 0..1000 {
+        let (a, b) = (Vec3::random_unit_vector(), Vec3::random_unit_vector());
+        let (a, b) = (
+            direction(a.z().abs(), a.y().atan2(a.x())),
+            direction(b.z().abs(), b.y().atan2(b.x())),
+        );
+        let (ab, ba) = (material.eval(&rec, a, b), material.eval(&rec, b, a));
+        assert!(
+            ab.approx_eq_epsilon(ba, 1e-9 * (1.0 + ab.len())),
+            "{} {}",
+            ab,
+            ba
+        );
+    }
+}
+
+/// Pearson's chi-square test of the directions `scatter` samples against
+/// the counts `pdf` expects in each bin, with the absorbed rays as one more
+/// bin. The critical value comes from the Wilson-Hilferty approximation.
+fn check_chi_square(material: &dyn Material) {
+    let rec = hit();
+    let bin_z = 2.0 / THETA_BINS as f64;
+    let bin_phi = 2.0 * PI / PHI_BINS as f64;
+    for &cos in COS_WO.iter() {
+        let wo = wo(cos);
+        let mut observed = vec![0.0; THETA_BINS * PHI_BINS + 1];
+        random::reseed(4);
+        for _ in 0..SAMPLES {
+            let bin = match scatter(material, wo) {
+                Some((_, wi)) => {
+                    let i = (((wi.z() + 1.0) / bin_z) as usize).min(THETA_BINS - 1);
+                    let j = (((wi.y().atan2(wi.x()) + PI) / bin_phi) as usize).min(PHI_BINS - 1);
+                    i * PHI_BINS + j
+                }
+                None => THETA_BINS * PHI_BINS,
+            };
+            observed[bin] += 1.0;
+        }
+
+        let mut expected: Vec<f64> = (0..THETA_BINS * PHI_BINS)
+            .map(|bin| {
+                let (i, j) = ((bin / PHI_BINS) as f64, (bin % PHI_BINS) as f64);
+                let z = (-1.0 + i * bin_z, -1.0 + (i + 1.0) * bin_z);
+                let phi = (-PI + j * bin_phi, -PI + (j + 1.0) * bin_phi);
+                let pdf = |wi| Color::ones() * material.pdf(&rec, wo, wi);
+                SAMPLES as f64 * integrate(pdf, z, phi, 16)[0]
+            })
+            .collect();
+        let absorbed = SAMPLES as f64 - expected.iter().sum::<f64>();
+        expected.push(absorbed.max(0.0));
+
+        let (mut chi2, mut bins) = (0.0, 0);
+        let (mut pooled_observed, mut pooled_expected) = (0.0, 0.0);
+        for (o, e) in observed.iter().zip(&expected) {
+            if *e < MIN_EXPECTED {
+                pooled_observed += o;
+                pooled_expected += e;
+            } else {
+                chi2 += (o - e) * (o - e) / e;
+                bins += 1;
+            }
+        }
+        if pooled_expected >= MIN_EXPECTED {
+            chi2 += (pooled_observed - pooled_expected).powi(2) / pooled_expected;
+            bins += 1;
+        } else {
+            assert!(
+                pooled_observed < 2.0 * MIN_EXPECTED,
+                "{} samples where none were expected",
+                pooled_observed
+            );
+        }
+
+        let dof = (bins - 1) as f64;
+        let critical =
+            dof * (1.0 - 2.0 / (9.0 * dof) + Z_SIGNIFICANCE * (2.0 / (9.0 * dof)).sqrt()).powi(3);
+        assert!(
+            chi2 < critical,
+            "chi-square {} above {} with {} degrees of freedom at cos {}",
+            chi2,
+            critical,
+            dof,
+            cos
+        );
+    }
+}
+
+#[test]
+fn lambertian() {
+    let white = Lambertian::new(Color::ones());
+    for energy in check_energy(&white) {
+        assert!(energy.approx_eq(Color::ones()));
+    }
+    let material = Lambertian::new(Color::new(0.8, 0.5, 0.2));
+    check_eval_and_pdf(&material);
+    check_reciprocity(&material);
+    check_chi_square(&material);
+}
+
+/// The fuzz isn't reciprocal, so it's left out of `check_reciprocity`: the
+/// lobe is the same both ways, but `eval` divides it by the cosine of `wi`
+/// alone. Multiplying that cosine back has to give the same both ways.
+fn check_fuzzy_metal(fuzz: f64) {
+    let white = Metal::new(Color::ones(), fuzz);
+    for energy in check_energy(&white) {
+        assert!(energy[0] > 0.3);
+    }
+    let material = Metal::new(Color::new(0.9, 0.6, 0.3), fuzz);
+    check_eval_and_pdf(&material);
+    check_chi_square(&material);
+
+    let rec = hit();
+    random::reseed(3);
+    for _ in 0..1000 {
+        let (a, b) = (Vec3::random_unit_vector(), Vec3::random_unit_vector());
+        let (a, b) = (
+            direction(a.z().abs(), a.y().atan2(a.x())),
+            direction(b.z().abs(), b.y().atan2(b.x())),
+        );
+        let ab = material.eval(&rec, a, b) * rec.normal.dot(b);
+        let ba = material.eval(&rec, b, a) * rec.normal.dot(a);
+        assert!(
+            ab.approx_eq_epsilon(ba, 1e-9 * (1.0 + ab.len())),
+            "{} {}",
+            ab,
+            ba
+        );
+    }
+}
+
+#[test]
+fn slightly_fuzzy_metal() {
+    check_fuzzy_metal(0.3);
+}
+
+#[test]
+fn very_fuzzy_metal() {
+    check_fuzzy_metal(0.8);
+}
+
+#[test]
+fn delta_lobes() {
+    let materials: [Arc<dyn Material>; 2] = [
+        Arc::new(Metal::new(Color::ones(), 0.0)),
+        Arc::new(Dielectric::new(1.5)),
+    ];
+    let rec = hit();
+    for material in materials.iter() {
+        check_energy(material.as_ref());
+        for &cos in COS_WO.iter() {
+            let wo = wo(cos);
+            random::reseed(5);
+            let (_, wi) = scatter(material.as_ref(), wo).unwrap();
+            assert_eq!(material.pdf(&rec, wo, wi), 0.0);
+            assert!(material.eval(&rec, wo, wi).approx_cero());
+        }
+    }
+    // Glass keeps all the light, reflected or refracted
+    for energy in check_energy(&Dielectric::new(1.5)) {
+        assert!(energy.approx_eq(Color::ones()));
+    }
+}
@@ -1,3 +1,5 @@
+use std::f64::consts::PI;
+
 use super::Material;
 use crate::{Color, Ray, Vec3};
 
@@ -14,7 +16,7 @@ impl Lambertian {
 impl Material for Lambertian {
     fn scatter(
         &self,
-        _ray: &crate::Ray,
+        ray: &crate::Ray,
         rec: &mut crate::HitRecord,
         attenuation: &mut Color,
         scattered: &mut crate::Ray,
@@ -25,9 +27,26 @@ impl Material for Lambertian {
             direction = rec.normal;
         }
 
-        *scattered = Ray::new(rec.p, direction);
+        *scattered = Ray::with_time(rec.p, direction, ray.time());
         *attenuation = self.albedo;
 
         true
     }
+
+    fn albedo(&self, _rec: &crate::HitRecord) -> Color {
+        self.albedo
+    }
+
+    fn eval(&self, rec: &crate::HitRecord, _wo: Vec3, wi: Vec3) -> Color {
+        if rec.normal.dot(wi) > 0.0 {
+            self.albedo / PI
+        } else {
+            Color::ceros()
+        }
+    }
+
+    /// Cosine weighted, as the normal plus a random unit vector gives.
+    fn pdf(&self, rec: &crate::HitRecord, _wo: Vec3, wi: Vec3) -> f64 {
+        rec.normal.dot(wi).max(0.0) / PI
+    }
 }
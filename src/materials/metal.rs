@@ -1,3 +1,5 @@
+use std::f64::consts::PI;
+
 use super::Material;
 use crate::{Color, Ray, Vec3};
 
@@ -22,9 +24,52 @@ impl Material for Metal {
     ) -> bool {
         let reflected = Vec3::reflect(ray.direction().unit_vector(), rec.normal);
 
-        *scattered = Ray::new(rec.p, reflected + Vec3::random_in_unit_sphere() * self.fuzz);
+        *scattered = Ray::with_time(
+            rec.p,
+            reflected + Vec3::random_in_unit_sphere() * self.fuzz,
+            ray.time(),
+        );
         *attenuation = self.albedo;
 
         rec.normal.dot(scattered.direction()) > 0.0
     }
+
+    fn albedo(&self, _rec: &crate::HitRecord) -> Color {
+        self.albedo
+    }
+
+    fn eval(&self, rec: &crate::HitRecord, wo: Vec3, wi: Vec3) -> Color {
+        let cos = rec.normal.dot(wi);
+        if cos <= 0.0 {
+            return Color::ceros();
+        }
+        self.albedo * (self.pdf(rec, wo, wi) / cos)
+    }
+
+    /// The scattered direction points from the origin to a random point of
+    /// the ball of radius `fuzz` around the mirror direction, so its density
+    /// is the part of the ball along `wi`, weighted by the squared distance.
+    /// A metal without fuzz is a delta lobe.
+    fn pdf(&self, rec: &crate::HitRecord, wo: Vec3, wi: Vec3) -> f64 {
+        if self.fuzz <= 0.0 || rec.normal.dot(wi) <= 0.0 {
+            return 0.0;
+        }
+        let reflected = Vec3::reflect(-wo, rec.normal);
+        let c = wi.dot(reflected);
+        let discriminant = c * c - 1.0 + self.fuzz * self.fuzz;
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+        let near = (c - discriminant.sqrt()).max(0.0);
+        let far = c + discriminant.sqrt();
+        if far <= 0.0 {
+            return 0.0;
+        }
+        // Integral of t^2 from `near` to `far` over the volume of the ball
+        (far.powi(3) - near.powi(3)) / (4.0 * PI * self.fuzz.powi(3))
+    }
+
+    fn is_specular(&self) -> bool {
+        true
+    }
 }
@@ -1,6 +1,8 @@
-use crate::{Color, HitRecord, Ray};
+use crate::{Color, HitRecord, Ray, Vec3};
 
 mod dielectric;
+#[cfg(test)]
+mod harness;
 mod lambertian;
 mod metal;
 
@@ -16,4 +18,30 @@ pub trait Material: Send + Sync {
         attenuation: &mut Color,
         scattered: &mut Ray,
     ) -> bool;
+
+    /// Color of the surface at `rec` for the albedo pass.
+    fn albedo(&self, _rec: &HitRecord) -> Color {
+        Color::ones()
+    }
+
+    /// BSDF at `rec` between the unit directions `wo`, towards where the
+    /// ray came from, and `wi`, where it scatters to. `scatter` gives
+    /// `eval * cos(wi) / pdf` as the attenuation. Delta lobes, like perfect
+    /// mirrors, aren't included and give 0.
+    fn eval(&self, _rec: &HitRecord, _wo: Vec3, _wi: Vec3) -> Color {
+        Color::ceros()
+    }
+
+    /// Density per solid angle of `scatter` sending the ray from `wo` to
+    /// `wi`, 0 for delta lobes. It integrates to the chance that `scatter`
+    /// doesn't absorb the ray.
+    fn pdf(&self, _rec: &HitRecord, _wo: Vec3, _wi: Vec3) -> f64 {
+        0.0
+    }
+
+    /// Whether the material scatters in a narrow lobe around a mirror or
+    /// refracted direction, rather than diffusely.
+    fn is_specular(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,56 @@
+use crate::Vec3;
+
+/// Orthonormal basis built around a single direction, used to move between
+/// world space and the local frame of a primitive or a scattering event.
+#[derive(Clone, Copy)]
+pub struct Onb {
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+}
+
+impl Onb {
+    pub fn from_w(w: Vec3) -> Self {
+        let w = w.unit_vector();
+        let a = if w.x().abs() > 0.9 {
+            Vec3::new(0.0, 1.0, 0.0)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let v = w.cross(a).unit_vector();
+        let u = v.cross(w);
+
+        Self { u, v, w }
+    }
+
+    pub fn w(&self) -> Vec3 {
+        self.w
+    }
+
+    /// Transforms local coordinates (`u`, `v`, `w`) into world space.
+    pub fn to_world(self, a: Vec3) -> Vec3 {
+        self.u * a.x() + self.v * a.y() + self.w * a.z()
+    }
+
+    /// Transforms a world space vector into local (`u`, `v`, `w`) coordinates.
+    pub fn to_local(self, a: Vec3) -> Vec3 {
+        Vec3::new(a.dot(self.u), a.dot(self.v), a.dot(self.w))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preserves_length() {
+        let onb = Onb::from_w(Vec3::new(1.0, 2.0, 3.0));
+        let a = Vec3::new(-4.0, 0.5, 2.0);
+
+        let local = onb.to_local(a);
+
+        assert!(float_eq!(local.len(), a.len(), 1e-12));
+        assert!(float_eq!(local.z(), a.dot(onb.w()), 1e-12));
+        assert!(float_eq!(onb.w().len(), 1.0, 1e-12));
+    }
+}
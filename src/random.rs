@@ -0,0 +1,78 @@
+use std::cell::RefCell;
+
+use rand::{
+    distributions::{Distribution, Standard},
+    rngs::StdRng,
+    Rng, SeedableRng,
+};
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Restarts the random numbers of the current thread from `seed`, which
+/// makes whatever runs next on it repeatable.
+#[cfg(test)]
+pub(crate) fn reseed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Runs `f` with random numbers of its own, started from `seed`. The ones
+/// the thread had are put back after, so nothing `f` does leaks into later
+/// work on the thread.
+pub(crate) fn with_seed<T>(seed: u64, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<StdRng>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            if let Some(previous) = self.0.take() {
+                RNG.with(|rng| *rng.borrow_mut() = previous);
+            }
+        }
+    }
+
+    let previous = RNG.with(|rng| rng.replace(StdRng::seed_from_u64(seed)));
+    let _restore = Restore(Some(previous));
+    f()
+}
+
+/// Runs `f` with the random number generator of the current thread.
+pub(crate) fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+pub(crate) fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    with_rng(|rng| rng.gen())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeatable_after_reseed() {
+        reseed(42);
+        let a: Vec<f64> = (0..10).map(|_| random()).collect();
+        reseed(42);
+        let b: Vec<f64> = (0..10).map(|_| random()).collect();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn seeded_work_leaves_the_thread_alone() {
+        reseed(7);
+        let expected: Vec<f64> = (0..10).map(|_| random()).collect();
+
+        reseed(7);
+        let seeded = with_seed(1, || (0..10).map(|_| random::<f64>()).collect::<Vec<_>>());
+        let after: Vec<f64> = (0..10).map(|_| random()).collect();
+        assert_eq!(after, expected);
+        assert_eq!(
+            seeded,
+            with_seed(1, || (0..10).map(|_| random::<f64>()).collect::<Vec<_>>())
+        );
+    }
+}
@@ -3,11 +3,21 @@ use crate::{vec3::Vec3, Point};
 pub struct Ray {
     origin: Point,
     direction: Vec3,
+    time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vec3) -> Self {
-        Self { origin, direction }
+        Self::with_time(origin, direction, 0.0)
+    }
+
+    /// Ray cast at `time` seconds, for animated objects.
+    pub fn with_time(origin: Point, direction: Vec3, time: f64) -> Self {
+        Self {
+            origin,
+            direction,
+            time,
+        }
     }
 
     pub fn origin(&self) -> Vec3 {
@@ -18,6 +28,10 @@ impl Ray {
         self.direction
     }
 
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
     pub fn at(&self, t: f64) -> Vec3 {
         self.origin + self.direction * t
     }
@@ -0,0 +1,203 @@
+use crate::{shade, sky, Color, HitRecord, Hittable, Point, Ray, Vec3};
+
+/// Number of `Aov` variants, the values kept for each sample.
+pub(crate) const AOV_COUNT: usize = 12;
+
+/// Image rendered next to the main one for compositing and denoising. The
+/// first ones come from the first hit of the camera ray and are 0 for the
+/// sky. The light path splits add up to the main image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aov {
+    /// Camera space depth of the first hit: its distance in front of the
+    /// camera along the view direction. Panoramas give negative values
+    /// behind the camera.
+    Depth,
+    /// World space normal, facing the camera.
+    Normal,
+    Albedo,
+    /// World space position.
+    Position,
+    /// Texture coordinates in the red and green channels.
+    Uv,
+    /// ID given by `Tagged`. IDs aren't averaged: each pixel keeps the one
+    /// of its first sample.
+    ObjectId,
+    MaterialId,
+    /// Sky light reaching the camera after one diffuse bounce.
+    DirectDiffuse,
+    /// Light reaching the camera after a diffuse bounce and then more.
+    IndirectDiffuse,
+    DirectSpecular,
+    IndirectSpecular,
+    /// The sky seen straight from the camera.
+    Emission,
+}
+
+impl Aov {
+    pub const ALL: [Aov; AOV_COUNT] = [
+        Aov::Depth,
+        Aov::Normal,
+        Aov::Albedo,
+        Aov::Position,
+        Aov::Uv,
+        Aov::ObjectId,
+        Aov::MaterialId,
+        Aov::DirectDiffuse,
+        Aov::IndirectDiffuse,
+        Aov::DirectSpecular,
+        Aov::IndirectSpecular,
+        Aov::Emission,
+    ];
+
+    /// Name used in file names.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::Position => "position",
+            Aov::Uv => "uv",
+            Aov::ObjectId => "object_id",
+            Aov::MaterialId => "material_id",
+            Aov::DirectDiffuse => "direct_diffuse",
+            Aov::IndirectDiffuse => "indirect_diffuse",
+            Aov::DirectSpecular => "direct_specular",
+            Aov::IndirectSpecular => "indirect_specular",
+            Aov::Emission => "emission",
+        }
+    }
+
+    /// Whether samples keep their first value instead of being averaged.
+    pub(crate) fn is_id(&self) -> bool {
+        matches!(self, Aov::ObjectId | Aov::MaterialId)
+    }
+}
+
+/// The passes that add up to the main image.
+pub(crate) const LIGHT_PATHS: [Aov; 5] = [
+    Aov::DirectDiffuse,
+    Aov::IndirectDiffuse,
+    Aov::DirectSpecular,
+    Aov::IndirectSpecular,
+    Aov::Emission,
+];
+
+/// Where a camera is and which way it looks, to measure `Aov::Depth`.
+#[derive(Clone, Copy)]
+pub(crate) struct View {
+    pub origin: Point,
+    /// Unit vector.
+    pub forward: Vec3,
+}
+
+/// `trace` that also gives the value of every `Aov`, indexed by
+/// `Aov as usize`, with the depth seen from `view`. It takes the same
+/// random numbers, so the color is the one `trace` would give.
+pub(crate) fn trace_aovs(
+    ray: &Ray,
+    world: &dyn Hittable,
+    view: &View,
+    depth: i32,
+    rays: &mut u64,
+) -> (Color, [Color; AOV_COUNT]) {
+    let mut aovs = [Color::ceros(); AOV_COUNT];
+    if depth <= 0 {
+        return (Color::ceros(), aovs);
+    }
+
+    *rays += 1;
+    let mut rec = HitRecord::new();
+    if !world.hit(ray, 0.001, f64::INFINITY, &mut rec) {
+        aovs[Aov::Emission as usize] = sky(ray);
+        return (aovs[Aov::Emission as usize], aovs);
+    }
+
+    let material = rec.material.clone();
+    aovs[Aov::Depth as usize] = Color::ones() * (rec.p - view.origin).dot(view.forward);
+    aovs[Aov::Normal as usize] = rec.normal;
+    aovs[Aov::Albedo as usize] = material.albedo(&rec);
+    aovs[Aov::Position as usize] = rec.p;
+    aovs[Aov::Uv as usize] = Color::new(rec.u, rec.v, 0.0);
+    aovs[Aov::ObjectId as usize] = Color::ones() * rec.object_id as f64;
+    aovs[Aov::MaterialId as usize] = Color::ones() * rec.material_id as f64;
+
+    let mut scattered = Ray::new(Point::ceros(), Vec3::ceros());
+    let mut attenuation = Color::ceros();
+    if !material.scatter(ray, &mut rec, &mut attenuation, &mut scattered) || depth == 1 {
+        return (Color::ceros(), aovs);
+    }
+
+    // Same as `trace` for the scattered ray, but telling apart whether it
+    // reaches the sky or another surface
+    let (direct, indirect) = if material.is_specular() {
+        (Aov::DirectSpecular, Aov::IndirectSpecular)
+    } else {
+        (Aov::DirectDiffuse, Aov::IndirectDiffuse)
+    };
+    *rays += 1;
+    let mut next = HitRecord::new();
+    if world.hit(&scattered, 0.001, f64::INFINITY, &mut next) {
+        aovs[indirect as usize] =
+            attenuation * shade(&scattered, &mut next, world, depth - 1, rays);
+    } else {
+        aovs[direct as usize] = attenuation * sky(&scattered);
+    }
+
+    (aovs[direct as usize] + aovs[indirect as usize], aovs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hittables::Plane, materials::Lambertian, random, trace};
+    use std::sync::Arc;
+
+    #[test]
+    fn same_color_as_trace() {
+        let world = crate::random_scene(&crate::SceneConfig {
+            small_sphere_count: 20,
+            seed: Some(4),
+            ..Default::default()
+        })
+        .unwrap();
+
+        for i in 0..50 {
+            let ray = Ray::new(
+                Point::new(13.0, 2.0, 3.0),
+                Vec3::new(-13.0, -2.0 + i as f64 * 0.05, -3.0),
+            );
+            random::reseed(i);
+            let color = trace(&ray, &world, 10, &mut 0);
+            random::reseed(i);
+            let view = View {
+                origin: ray.origin(),
+                forward: ray.direction().unit_vector(),
+            };
+            let (with_aovs, aovs) = trace_aovs(&ray, &world, &view, 10, &mut 0);
+
+            assert!(color.approx_eq(with_aovs));
+            let split = LIGHT_PATHS
+                .iter()
+                .fold(Color::ceros(), |sum, &aov| sum + aovs[aov as usize]);
+            assert!(color.approx_eq(split));
+        }
+    }
+
+    #[test]
+    fn depth_along_the_view() {
+        let world = Plane::new(
+            Point::new(0.0, 0.0, -4.0),
+            Vec3::new(0.0, 0.0, 1.0),
+            Arc::new(Lambertian::new(Color::ones())),
+        );
+        let view = View {
+            origin: Point::ceros(),
+            forward: Vec3::new(0.0, 0.0, -1.0),
+        };
+
+        // 5 units away, but only 4 in front of the camera
+        let ray = Ray::new(Point::ceros(), Vec3::new(3.0, 0.0, -4.0));
+        let (_, aovs) = trace_aovs(&ray, &world, &view, 1, &mut 0);
+        assert!(aovs[Aov::Depth as usize].approx_eq(Color::ones() * 4.0));
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use super::{
+    film::{read_u32, read_u64},
+    Film,
+};
+
+const MAGIC: &[u8; 4] = b"RTCK";
+const VERSION: u32 = 2;
+
+/// State of a progressive render after some passes, which together with
+/// its film is enough to continue it and get the same image as if it had
+/// never stopped.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Checkpoint {
+    /// Passes done, which also gives the seed of the next one.
+    pub passes: u64,
+    /// Samples each pixel got so far.
+    pub samples: u32,
+    /// Seed the scene was generated with.
+    pub scene_seed: u64,
+}
+
+impl Checkpoint {
+    /// Writes to a temporary file first and then renames it, so a render
+    /// killed while saving still leaves the previous checkpoint.
+    pub fn save<P: AsRef<Path>>(&self, path: P, film: &Film) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+
+        {
+            let mut w = BufWriter::new(File::create(&tmp)?);
+            w.write_all(MAGIC)?;
+            w.write_all(&VERSION.to_le_bytes())?;
+            w.write_all(&self.passes.to_le_bytes())?;
+            w.write_all(&self.samples.to_le_bytes())?;
+            w.write_all(&self.scene_seed.to_le_bytes())?;
+            film.write_to(&mut w)?;
+            w.flush()?;
+        }
+
+        fs::rename(&tmp, path)
+    }
+
+    /// Loads a checkpoint of a `width` by `height` image.
+    pub fn load<P: AsRef<Path>>(path: P, width: u32, height: u32) -> io::Result<(Self, Film)> {
+        let mut r = BufReader::new(File::open(path)?);
+
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u32(&mut r)? != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Not a checkpoint file, or from another version",
+            ));
+        }
+
+        let checkpoint = Self {
+            passes: read_u64(&mut r)?,
+            samples: read_u32(&mut r)?,
+            scene_seed: read_u64(&mut r)?,
+        };
+        Ok((checkpoint, Film::read_from(&mut r, width, height)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Color;
+
+    #[test]
+    fn round_trip() {
+        let mut film = Film::new(3, 2);
+        film.add_sample(2, 1, Color::new(0.1, 0.2, 0.3));
+        film.add_sample(2, 1, Color::new(0.7, 0.5, 0.3));
+        let checkpoint = Checkpoint {
+            passes: 7,
+            samples: 28,
+            scene_seed: 1234,
+        };
+
+        let path = std::env::temp_dir().join("ray_tracing_round_trip.checkpoint");
+        checkpoint.save(&path, &film).unwrap();
+        let (loaded, loaded_film) = Checkpoint::load(&path, 3, 2).unwrap();
+        let other_size = Checkpoint::load(&path, 30, 20);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            other_size,
+            Err(e) if e.kind() == io::ErrorKind::InvalidData
+        ));
+
+        assert_eq!(loaded, checkpoint);
+        assert_eq!(loaded_film.samples(2, 1), 2);
+        assert!(loaded_film
+            .pixel(2, 1)
+            .approx_eq(Color::new(0.4, 0.35, 0.3)));
+        assert_eq!(loaded_film.pixel_error(2, 1), film.pixel_error(2, 1));
+    }
+}
@@ -0,0 +1,173 @@
+use rayon::prelude::*;
+
+use super::{Aov, Film};
+use crate::{Color, DenoiseConfig, Error, Image, Result};
+
+/// Weights of the 5 taps of the B3 spline the filter is built on.
+const KERNEL: [f64; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/// Keeps the edge-stopping functions finite for noiseless pixels.
+const EPSILON: f64 = 1e-10;
+
+/// Removes noise from the image of `film` with an edge-avoiding à-trous
+/// wavelet filter (Dammertz et al. 2010), in linear color.
+///
+/// Each iteration blends every pixel with 5x5 neighbors spread twice as far
+/// apart as in the last one. Neighbors count less the more their normal,
+/// depth and albedo differ, so edges stay sharp, and the more their
+/// luminance differs relative to the noise in the pixel, estimated from the
+/// variance of its samples and filtered along with the colors. The film
+/// needs the AOVs.
+pub fn denoise(film: &Film, config: &DenoiseConfig) -> Result<Image> {
+    let aov = |aov| {
+        film.aov(aov)
+            .ok_or_else(|| Error::InvalidConfig("Denoising needs a film with the AOVs".to_string()))
+    };
+    let (normal, depth, albedo) = (aov(Aov::Normal)?, aov(Aov::Depth)?, aov(Aov::Albedo)?);
+    let (width, height) = (film.width() as i64, film.height() as i64);
+
+    // Few samples give poor variances, so they are compared after a 3x3
+    // gaussian blur
+    let blurred = |variances: &[f64], px: i64, py: i64| {
+        let (mut sum, mut total) = (0.0, 0.0);
+        for qy in (py - 1).max(0)..=(py + 1).min(height - 1) {
+            for qx in (px - 1).max(0)..=(px + 1).min(width - 1) {
+                let w = 1.0 / (1 << ((qx - px).abs() + (qy - py).abs())) as f64;
+                sum += w * variances[(qy * width + qx) as usize];
+                total += w;
+            }
+        }
+        sum / total
+    };
+
+    let mut colors = film.to_colors();
+    let mut variances: Vec<f64> = (0..film.height())
+        .flat_map(|y| (0..film.width()).map(move |x| (x, y)))
+        .map(|(x, y)| film.variance(x, y))
+        .collect();
+
+    for iteration in 0..config.iterations {
+        let step = 1 << iteration;
+        let (filtered, filtered_variances): (Vec<Color>, Vec<f64>) = (0..width * height)
+            .into_par_iter()
+            .map(|p| {
+                let (px, py) = (p % width, p / width);
+                let i = p as usize;
+                let (l_p, sigma_l) = (
+                    colors[i].luminance(),
+                    config.sigma_luminance * blurred(&variances, px, py).sqrt() + EPSILON,
+                );
+                let (n_p, z_p, a_p) =
+                    (normal.pixels()[i], depth.pixels()[i][0], albedo.pixels()[i]);
+
+                let mut sum = Color::ceros();
+                let mut variance = 0.0;
+                let mut total = 0.0;
+                for (dy, ky) in KERNEL.iter().enumerate() {
+                    let qy = py + (dy as i64 - 2) * step;
+                    if qy < 0 || qy >= height {
+                        continue;
+                    }
+                    for (dx, kx) in KERNEL.iter().enumerate() {
+                        let qx = px + (dx as i64 - 2) * step;
+                        if qx < 0 || qx >= width {
+                            continue;
+                        }
+                        let j = (qy * width + qx) as usize;
+
+                        let w_l = (-(l_p - colors[j].luminance()).abs() / sigma_l).exp();
+                        let w_n = n_p
+                            .dot(normal.pixels()[j])
+                            .max(0.0)
+                            .powf(config.sigma_normal);
+                        let z_q = depth.pixels()[j][0];
+                        let w_z = (-(z_p - z_q).abs()
+                            / (config.sigma_depth * z_p.max(z_q) + EPSILON))
+                            .exp();
+                        let w_a = (-(a_p - albedo.pixels()[j]).len2()
+                            / (config.sigma_albedo * config.sigma_albedo))
+                            .exp();
+                        // The sky has no normal, and only matches itself
+                        let w_n = if z_p == 0.0 && z_q == 0.0 { 1.0 } else { w_n };
+
+                        let w = kx * ky * w_l * w_n * w_z * w_a;
+                        if w > 0.0 {
+                            sum += colors[j] * w;
+                            variance += w * w * variances[j];
+                            total += w;
+                        }
+                    }
+                }
+
+                // The pixel itself always has weight, so `total` isn't 0
+                (sum / total, variance / (total * total))
+            })
+            .unzip();
+        colors = filtered;
+        variances = filtered_variances;
+    }
+
+    Image::new(film.width(), film.height(), colors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{render::aov::AOV_COUNT, Vec3};
+
+    /// Film with AOVs 2 units in front of the camera, where each pixel has
+    /// the mean and normal `pixel` gives it and samples 0.3 away from it.
+    fn film(pixel: impl Fn(u32, u32) -> (f64, Vec3)) -> Film {
+        let mut film = Film::with_aovs(16, 16);
+        let mut aovs = [Color::ceros(); AOV_COUNT];
+        aovs[Aov::Depth as usize] = Color::ones() * 2.0;
+        aovs[Aov::Albedo as usize] = Color::ones() * 0.5;
+        for y in 0..16 {
+            for x in 0..16 {
+                let (mean, normal) = pixel(x, y);
+                aovs[Aov::Normal as usize] = normal;
+                for s in 0..4 {
+                    let noise = if s % 2 == 0 { 0.3 } else { -0.3 };
+                    film.add_sample_with_aovs(x, y, Color::ones() * (mean + noise), &aovs);
+                }
+            }
+        }
+        film
+    }
+
+    fn checker(x: u32, y: u32) -> f64 {
+        if (x + y) % 2 == 0 {
+            0.05
+        } else {
+            -0.05
+        }
+    }
+
+    #[test]
+    fn smooths_noise() {
+        let front = Vec3::new(0.0, 0.0, 1.0);
+        let noisy = film(|x, y| (0.5 + checker(x, y), front));
+        let denoised = denoise(&noisy, &DenoiseConfig::default()).unwrap();
+        for c in denoised.pixels() {
+            assert!(float_eq!(c[0], 0.5, 0.01), "{}", c);
+        }
+
+        assert!(denoise(&Film::new(4, 4), &DenoiseConfig::default()).is_err());
+    }
+
+    #[test]
+    fn keeps_edges() {
+        let noisy = film(|x, y| {
+            if x < 8 {
+                (0.2 + checker(x, y), Vec3::new(0.0, 0.0, 1.0))
+            } else {
+                (0.8 + checker(x, y), Vec3::new(1.0, 0.0, 0.0))
+            }
+        });
+        let denoised = denoise(&noisy, &DenoiseConfig::default()).unwrap();
+        for y in 0..16 {
+            assert!(float_eq!(denoised.pixel(7, y)[0], 0.2, 0.02));
+            assert!(float_eq!(denoised.pixel(8, y)[0], 0.8, 0.02));
+        }
+    }
+}
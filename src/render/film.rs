@@ -0,0 +1,383 @@
+use std::io::{self, Read, Write};
+
+use super::aov::{Aov, AOV_COUNT};
+use crate::{Color, Error, Image, Result};
+
+/// Floor for the brightness the relative error is divided by, so black
+/// pixels don't dominate it.
+const ERROR_MIN_LUMINANCE: f64 = 0.01;
+
+/// Accumulated samples for each pixel of an image, or of a part of it, in
+/// linear (HDR) color. Rows go from the top of the image to the bottom.
+#[derive(Clone)]
+pub struct Film {
+    width: u32,
+    height: u32,
+    /// Sum of the samples of each pixel.
+    pixels: Vec<Color>,
+    /// Running mean and sum of squared differences from it of the
+    /// luminance of the samples (Welford's algorithm), for their variance.
+    means: Vec<f64>,
+    m2: Vec<f64>,
+    samples: Vec<u32>,
+    /// Sum of the samples of every `Aov` for each pixel, `AOV_COUNT` values
+    /// per pixel, or empty when they aren't rendered. IDs hold their first
+    /// sample instead.
+    aovs: Vec<Color>,
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = (width * height) as usize;
+        Self {
+            width,
+            height,
+            pixels: vec![Color::ceros(); len],
+            means: vec![0.0; len],
+            m2: vec![0.0; len],
+            samples: vec![0; len],
+            aovs: Vec::new(),
+        }
+    }
+
+    /// Film that also keeps every `Aov`.
+    pub fn with_aovs(width: u32, height: u32) -> Self {
+        Self {
+            aovs: vec![Color::ceros(); (width * height) as usize * AOV_COUNT],
+            ..Self::new(width, height)
+        }
+    }
+
+    pub fn has_aovs(&self) -> bool {
+        !self.aovs.is_empty()
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Average of the samples of a pixel.
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let i = (y * self.width + x) as usize;
+        self.pixels[i] / self.samples[i].max(1) as f64
+    }
+
+    pub fn samples(&self, x: u32, y: u32) -> u32 {
+        self.samples[(y * self.width + x) as usize]
+    }
+
+    pub fn add_sample(&mut self, x: u32, y: u32, color: Color) {
+        let i = (y * self.width + x) as usize;
+        self.pixels[i] += color;
+        self.samples[i] += 1;
+
+        let l = color.luminance();
+        let delta = l - self.means[i];
+        self.means[i] += delta / self.samples[i] as f64;
+        self.m2[i] += delta * (l - self.means[i]);
+    }
+
+    /// `add_sample` with the value of every `Aov` for the sample, indexed
+    /// by `Aov as usize`.
+    pub fn add_sample_with_aovs(
+        &mut self,
+        x: u32,
+        y: u32,
+        color: Color,
+        aovs: &[Color; AOV_COUNT],
+    ) {
+        self.add_sample(x, y, color);
+        let i = (y * self.width + x) as usize;
+        let first = self.samples[i] == 1;
+        for aov in Aov::ALL.iter() {
+            let value = &mut self.aovs[i * AOV_COUNT + *aov as usize];
+            if !aov.is_id() {
+                *value += aovs[*aov as usize];
+            } else if first {
+                *value = aovs[*aov as usize];
+            }
+        }
+    }
+
+    /// Average of the samples of an `Aov`, or `None` if the film doesn't
+    /// keep them.
+    pub fn aov(&self, aov: Aov) -> Option<Image> {
+        if !self.has_aovs() {
+            return None;
+        }
+
+        let pixels = self
+            .samples
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| {
+                let value = self.aovs[i * AOV_COUNT + aov as usize];
+                if aov.is_id() {
+                    value
+                } else {
+                    value / n.max(1) as f64
+                }
+            })
+            .collect();
+        Some(Image::from_pixels(self.width, self.height, pixels))
+    }
+
+    /// Adds the samples of `other` with its top left corner at `(x, y)`.
+    /// Fails if it doesn't fit, or misses the AOVs this film keeps.
+    pub fn merge(&mut self, other: &Film, x: u32, y: u32) -> Result<()> {
+        let fits =
+            |at: u32, size: u32, total: u32| at.checked_add(size).is_some_and(|end| end <= total);
+        if !fits(x, other.width, self.width) || !fits(y, other.height, self.height) {
+            return Err(Error::InvalidConfig(format!(
+                "A {}x{} film at ({}, {}) doesn't fit in a {}x{} one",
+                other.width, other.height, x, y, self.width, self.height
+            )));
+        }
+        if self.has_aovs() && !other.has_aovs() {
+            return Err(Error::InvalidConfig(
+                "Merged film is missing the AOVs".to_string(),
+            ));
+        }
+
+        for row in 0..other.height {
+            let src = (row * other.width) as usize;
+            let dst = ((y + row) * self.width + x) as usize;
+            for k in 0..other.width as usize {
+                let (i, j) = (dst + k, src + k);
+                let (na, nb) = (self.samples[i] as f64, other.samples[j] as f64);
+                if nb == 0.0 {
+                    continue;
+                }
+
+                // Combines both means and variances as if all the samples
+                // had been added here
+                let n = na + nb;
+                let delta = other.means[j] - self.means[i];
+                self.means[i] += delta * nb / n;
+                self.m2[i] += other.m2[j] + delta * delta * na * nb / n;
+                if self.has_aovs() {
+                    for aov in Aov::ALL.iter() {
+                        let k = *aov as usize;
+                        let value = other.aovs[j * AOV_COUNT + k];
+                        let merged = &mut self.aovs[i * AOV_COUNT + k];
+                        if !aov.is_id() {
+                            *merged += value;
+                        } else if na == 0.0 {
+                            *merged = value;
+                        }
+                    }
+                }
+                self.pixels[i] += other.pixels[j];
+                self.samples[i] += other.samples[j];
+            }
+        }
+        Ok(())
+    }
+
+    /// Variance of the mean luminance of a pixel, or infinity with less
+    /// than two samples.
+    pub fn variance(&self, x: u32, y: u32) -> f64 {
+        let i = (y * self.width + x) as usize;
+        let n = self.samples[i] as f64;
+        if n < 2.0 {
+            return f64::INFINITY;
+        }
+
+        self.m2[i] / (n - 1.0) / n
+    }
+
+    /// Standard error of the mean luminance of a pixel relative to that
+    /// luminance, or infinity with less than two samples.
+    pub fn pixel_error(&self, x: u32, y: u32) -> f64 {
+        let mean = self.means[(y * self.width + x) as usize];
+        self.variance(x, y).sqrt() / mean.max(ERROR_MIN_LUMINANCE)
+    }
+
+    /// Average of `pixel_error` over the image.
+    pub fn error(&self) -> f64 {
+        let total: f64 = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|(x, y)| self.pixel_error(x, y))
+            .sum();
+        total / (self.width * self.height) as f64
+    }
+
+    /// Writes the exact state of the film, to be read back by `read_from`.
+    pub(crate) fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.width.to_le_bytes())?;
+        w.write_all(&self.height.to_le_bytes())?;
+        w.write_all(&[self.has_aovs() as u8])?;
+        for i in 0..self.pixels.len() {
+            for a in 0..3 {
+                w.write_all(&self.pixels[i][a].to_le_bytes())?;
+            }
+            w.write_all(&self.means[i].to_le_bytes())?;
+            w.write_all(&self.m2[i].to_le_bytes())?;
+            w.write_all(&self.samples[i].to_le_bytes())?;
+        }
+        for value in self.aovs.iter() {
+            for a in 0..3 {
+                w.write_all(&value[a].to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a film written by `write_to`, which must be `width` by
+    /// `height`. The size is checked before anything is allocated, since it
+    /// may come from another machine or a damaged file.
+    pub(crate) fn read_from<R: Read>(r: &mut R, width: u32, height: u32) -> io::Result<Self> {
+        if read_u32(r)? != width || read_u32(r)? != height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected a film of {}x{}", width, height),
+            ));
+        }
+        let mut has_aovs = [0];
+        r.read_exact(&mut has_aovs)?;
+        let mut film = if has_aovs[0] != 0 {
+            Film::with_aovs(width, height)
+        } else {
+            Film::new(width, height)
+        };
+        for i in 0..film.pixels.len() {
+            for a in 0..3 {
+                film.pixels[i][a] = read_f64(r)?;
+            }
+            film.means[i] = read_f64(r)?;
+            film.m2[i] = read_f64(r)?;
+            film.samples[i] = read_u32(r)?;
+        }
+        for value in film.aovs.iter_mut() {
+            for a in 0..3 {
+                value[a] = read_f64(r)?;
+            }
+        }
+        Ok(film)
+    }
+
+    /// Gray 8 bit RGB values showing the samples taken for each pixel,
+    /// white being `max_samples`.
+    pub fn samples_to_rgb8(&self, max_samples: u32) -> Vec<u8> {
+        self.samples
+            .iter()
+            .flat_map(|&n| {
+                let v = (n.min(max_samples) as f64 / max_samples.max(1) as f64 * 255.0) as u8;
+                vec![v, v, v]
+            })
+            .collect()
+    }
+
+    /// Average of each pixel, row by row from the top.
+    pub fn to_colors(&self) -> Vec<Color> {
+        self.pixels
+            .iter()
+            .zip(&self.samples)
+            .map(|(&c, &n)| c / n.max(1) as f64)
+            .collect()
+    }
+
+    pub fn to_image(&self) -> Image {
+        Image::from_pixels(self.width, self.height, self.to_colors())
+    }
+
+    /// 8 bit RGB values of the average of each pixel.
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .zip(&self.samples)
+            .flat_map(|(c, &n)| {
+                let n = n.max(1);
+                vec![c.r(n), c.g(n), c.b(n)]
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn read_u32<R: Read>(r: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+pub(crate) fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_f64<R: Read>(r: &mut R) -> io::Result<f64> {
+    read_u64(r).map(f64::from_bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_at_offset() {
+        let mut film = Film::new(4, 3);
+        let mut tile = Film::new(2, 2);
+        tile.add_sample(1, 0, Color::ones());
+        tile.add_sample(1, 0, Color::ones());
+
+        film.merge(&tile, 2, 1).unwrap();
+        assert!(film.pixel(3, 1).approx_eq(Color::ones()));
+        assert_eq!(film.samples(3, 1), 2);
+        assert!(film.pixel(1, 0).approx_eq(Color::ceros()));
+        assert_eq!(film.to_rgb8()[(4 + 3) * 3], 255);
+
+        assert!(film.merge(&tile, 3, 1).is_err());
+        assert!(film.merge(&tile, 2, u32::MAX).is_err());
+    }
+
+    #[test]
+    fn error_shrinks_with_samples() {
+        let mut film = Film::new(1, 1);
+        assert!(film.pixel_error(0, 0).is_infinite());
+
+        // Alternating 0 and 1 has a standard deviation of about 0.5
+        for i in 0..100 {
+            film.add_sample(0, 0, Color::ones() * (i % 2) as f64);
+        }
+        let error = film.pixel_error(0, 0);
+        assert!(float_eq!(error, 0.1005, 1e-3), "{}", error);
+
+        // Merged in parts, the result is the same
+        let mut part = Film::new(1, 1);
+        for i in 0..300 {
+            part.add_sample(0, 0, Color::ones() * (i % 2) as f64);
+        }
+        film.merge(&part, 0, 0).unwrap();
+        assert!(float_eq!(film.error(), error / 2.0, 1e-3));
+
+        let mut whole = Film::new(1, 1);
+        for i in 0..400 {
+            whole.add_sample(0, 0, Color::ones() * (i % 2) as f64);
+        }
+        assert!(float_eq!(film.error(), whole.error(), 1e-12));
+    }
+
+    #[test]
+    fn read_checks_the_size() {
+        let mut film = Film::with_aovs(3, 2);
+        film.add_sample(1, 1, Color::new(0.1, 0.2, 0.3));
+        let mut bytes = Vec::new();
+        film.write_to(&mut bytes).unwrap();
+
+        let read = Film::read_from(&mut &bytes[..], 3, 2).unwrap();
+        assert!(read.pixel(1, 1).approx_eq(Color::new(0.1, 0.2, 0.3)));
+        assert!(read.has_aovs());
+        assert!(Film::read_from(&mut &bytes[..], 2, 3).is_err());
+
+        // A huge size is refused before allocating anything for it
+        let mut huge = u32::MAX.to_le_bytes().to_vec();
+        huge.extend_from_slice(&u32::MAX.to_le_bytes());
+        huge.push(0);
+        assert!(Film::read_from(&mut &huge[..], 3, 2).is_err());
+    }
+}
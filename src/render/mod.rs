@@ -0,0 +1,481 @@
+use rand::Rng;
+use rayon::prelude::*;
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use crate::{random, trace, Camera, Color, Hittable, ImgConfig, Ray, Result};
+
+mod aov;
+mod checkpoint;
+mod denoise;
+mod film;
+mod monitor;
+mod post;
+mod tile;
+
+pub(crate) use aov::View;
+use aov::{trace_aovs, AOV_COUNT, LIGHT_PATHS};
+pub(crate) use checkpoint::Checkpoint;
+pub(crate) use monitor::Monitor;
+
+pub use aov::Aov;
+pub use denoise::denoise;
+pub use film::Film;
+pub use monitor::{CancelToken, Progress};
+pub use post::post_process;
+pub use tile::{tiles, Tile, TileOrder};
+
+/// What a render sees: the world through `cameras` while the shutter is
+/// open from `shutter.0` to `shutter.1` seconds. The shutter is split in as
+/// many equal parts as cameras, and each sample uses the camera for the part
+/// its time falls in.
+pub(crate) struct Shot<'a> {
+    pub world: &'a Arc<dyn Hittable>,
+    pub cameras: &'a [Arc<dyn Camera>],
+    /// Where each of `cameras` looks from, for the depth AOV.
+    pub views: &'a [View],
+    pub shutter: (f64, f64),
+    /// Scale for every sample.
+    pub exposure: f64,
+}
+
+/// Seed for pass `pass` of frame `frame` of a render seeded with `seed`.
+/// The three are hashed together so that no two frames or passes share
+/// their samples.
+pub(crate) fn shot_seed(seed: u64, frame: u64, pass: u64) -> u64 {
+    mix(mix(mix(seed) ^ frame) ^ pass)
+}
+
+/// SplitMix64 finalizer.
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Seed for the random numbers of tile `index`, so every tile gets the same
+/// samples whichever thread renders it.
+pub(crate) fn tile_seed(seed: u64, index: usize) -> u64 {
+    seed.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        .wrapping_add(index as u64)
+}
+
+/// Empty film of `width` by `height`, keeping the AOVs if `img_config`
+/// asks for any or for denoising.
+pub(crate) fn new_film(img_config: &ImgConfig, width: u32, height: u32) -> Film {
+    if img_config.aovs.is_empty() && img_config.denoise.is_none() {
+        Film::new(width, height)
+    } else {
+        Film::with_aovs(width, height)
+    }
+}
+
+/// Renders one image with `img_config.samples_per_pixel` samples, or as
+/// `img_config.adaptive` says. See `render_pass`.
+pub(crate) fn render_frame(
+    img_config: &ImgConfig,
+    shot: &Shot,
+    seed: u64,
+    monitor: &Monitor,
+) -> Result<Film> {
+    let mut film = new_film(img_config, img_config.width, img_config.height());
+    let adaptive = match &img_config.adaptive {
+        Some(adaptive) => adaptive,
+        None => {
+            let samples = img_config.samples_per_pixel;
+            render_pass(img_config, shot, &|_, _| samples, seed, &mut film, monitor)?;
+            return Ok(film);
+        }
+    };
+
+    // Every pixel gets the minimum, and then the ones still too noisy get
+    // that many more in each pass until they are good enough or reach the
+    // maximum
+    let step = adaptive.min_samples.max(2);
+    let mut plan = vec![step.min(adaptive.max_samples); (film.width() * film.height()) as usize];
+    let mut pass = 0;
+    while plan.iter().any(|&n| n > 0) && !monitor.is_cancelled() {
+        let width = film.width();
+        render_pass(
+            img_config,
+            shot,
+            &|x, y| plan[(y * width + x) as usize],
+            shot_seed(seed, 0, pass),
+            &mut film,
+            monitor,
+        )?;
+        pass += 1;
+
+        for y in 0..film.height() {
+            for x in 0..width {
+                let taken = film.samples(x, y);
+                plan[(y * width + x) as usize] = if taken < adaptive.max_samples
+                    && film.pixel_error(x, y) > adaptive.target_error
+                {
+                    step.min(adaptive.max_samples - taken)
+                } else {
+                    0
+                };
+            }
+        }
+    }
+
+    Ok(film)
+}
+
+/// Adds `samples(x, y)` samples to each pixel of `film`.
+///
+/// Threads take tiles in the configured order as they become free, render
+/// each one on its own film and add it to `film` when done. Once cancelled,
+/// no more tiles are started.
+pub(crate) fn render_pass(
+    img_config: &ImgConfig,
+    shot: &Shot,
+    samples: &(dyn Fn(u32, u32) -> u32 + Sync),
+    seed: u64,
+    film: &mut Film,
+    monitor: &Monitor,
+) -> Result<()> {
+    let start = Instant::now();
+    let tiles = tiles(
+        img_config.width,
+        img_config.height(),
+        img_config.tile_size,
+        img_config.tile_order,
+    )?;
+    let pixels_total = img_config.width as u64 * img_config.height() as u64;
+    let film = Mutex::new(film);
+    let next = AtomicUsize::new(0);
+    let tiles_done = AtomicUsize::new(0);
+    let pixels_done = AtomicU64::new(0);
+
+    (0..rayon::current_num_threads())
+        .into_par_iter()
+        .try_for_each(|_| loop {
+            if monitor.is_cancelled() {
+                return Ok(());
+            }
+            let index = next.fetch_add(1, Ordering::Relaxed);
+            let tile = match tiles.get(index) {
+                Some(tile) => *tile,
+                None => return Ok(()),
+            };
+
+            let (tile_film, rays) =
+                render_tile(img_config, shot, samples, tile_seed(seed, index), tile);
+            film.lock().unwrap().merge(&tile_film, tile.x, tile.y)?;
+
+            let pixels = tile.width as u64 * tile.height as u64;
+            monitor.tile_done(
+                rays,
+                (tiles_done.fetch_add(1, Ordering::Relaxed) + 1, tiles.len()),
+                (
+                    pixels_done.fetch_add(pixels, Ordering::Relaxed) + pixels,
+                    pixels_total,
+                ),
+                start,
+            );
+        })
+}
+
+/// Film for `tile` with `samples(x, y)` samples for each of its pixels,
+/// with the random numbers started from `seed`, and the rays it took. See
+/// `render_pass`.
+pub(crate) fn render_tile(
+    img_config: &ImgConfig,
+    shot: &Shot,
+    samples: &(dyn Fn(u32, u32) -> u32 + Sync),
+    seed: u64,
+    tile: Tile,
+) -> (Film, u64) {
+    let width = img_config.width;
+    let height = img_config.height();
+    let (cameras, shutter) = (shot.cameras, shot.shutter);
+    let parts = cameras.len();
+
+    random::with_seed(seed, || {
+        let mut rays = 0;
+        let mut film = new_film(img_config, tile.width, tile.height);
+        for y in 0..tile.height {
+            // Rows of the image count from the top, `v` from the bottom
+            let j = height - 1 - (tile.y + y);
+            for x in 0..tile.width {
+                let i = tile.x + x;
+                for _ in 0..samples(i, tile.y + y) {
+                    let (u, v, part, jitter) = random::with_rng(|rng| {
+                        (
+                            (i as f64 + rng.gen::<f64>()) / (width - 1) as f64,
+                            (j as f64 + rng.gen::<f64>()) / (height - 1) as f64,
+                            rng.gen_range(0..parts),
+                            rng.gen::<f64>(),
+                        )
+                    });
+                    let time =
+                        shutter.0 + (shutter.1 - shutter.0) * (part as f64 + jitter) / parts as f64;
+
+                    let ray = cameras[part]
+                        .get_ray(u, v)
+                        .map(|ray| Ray::with_time(ray.origin(), ray.direction(), time));
+                    let world = &**shot.world;
+                    if film.has_aovs() {
+                        let (color, mut aovs) = match ray {
+                            Some(ray) => trace_aovs(
+                                &ray,
+                                world,
+                                &shot.views[part],
+                                img_config.max_depth,
+                                &mut rays,
+                            ),
+                            None => (Color::ceros(), [Color::ceros(); AOV_COUNT]),
+                        };
+                        for aov in LIGHT_PATHS.iter() {
+                            aovs[*aov as usize] *= shot.exposure;
+                        }
+                        film.add_sample_with_aovs(x, y, color * shot.exposure, &aovs);
+                    } else {
+                        let color = match ray {
+                            Some(ray) => trace(&ray, world, img_config.max_depth, &mut rays),
+                            None => Color::ceros(),
+                        };
+                        film.add_sample(x, y, color * shot.exposure);
+                    }
+                }
+            }
+        }
+
+        (film, rays)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hittables::{HittableList, Sphere},
+        materials::Lambertian,
+        AdaptiveConfig, CameraConfig, Point,
+    };
+
+    /// World, camera and view of a test scene.
+    type Scene = (Arc<dyn Hittable>, [Arc<dyn Camera>; 1], [View; 1]);
+
+    /// Diffuse sphere on the ground in the middle of a square image, with
+    /// sky above it.
+    fn sphere_scene() -> Scene {
+        let material = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let mut scene = HittableList::new();
+        scene.add(Arc::new(Sphere::new(Point::ceros(), 1.0, material.clone())));
+        scene.add(Arc::new(Sphere::new(
+            Point::new(0.0, -101.0, 0.0),
+            100.0,
+            material,
+        )));
+        let world: Arc<dyn Hittable> = Arc::new(scene);
+        let config = CameraConfig {
+            lookfrom: Point::new(0.0, 0.0, 5.0),
+            lookat: Point::ceros(),
+            vert_fov: 40.0,
+            aperture: 0.0,
+            ..CameraConfig::default()
+        };
+        let camera = config.camera(1.0, &*world, 0.0).unwrap();
+
+        (world, [camera], [config.view()])
+    }
+
+    #[test]
+    fn same_seed_same_image() {
+        let (world, cameras, views) = sphere_scene();
+        let img_config = ImgConfig {
+            width: 40,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 4,
+            tile_size: 8,
+            ..ImgConfig::default()
+        };
+
+        let shot = Shot {
+            world: &world,
+            cameras: &cameras,
+            views: &views,
+            shutter: (0.0, 0.0),
+            exposure: 1.0,
+        };
+        let render = |seed| {
+            render_frame(&img_config, &shot, seed, &Monitor::default())
+                .unwrap()
+                .to_rgb8()
+        };
+        assert_eq!(render(1), render(1));
+        assert_ne!(render(1), render(2));
+    }
+
+    #[test]
+    fn tiles_keep_their_seed_to_themselves() {
+        let (world, cameras, views) = sphere_scene();
+        let img_config = ImgConfig {
+            width: 16,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 2,
+            ..ImgConfig::default()
+        };
+        let shot = Shot {
+            world: &world,
+            cameras: &cameras,
+            views: &views,
+            shutter: (0.0, 0.0),
+            exposure: 1.0,
+        };
+        let tile = Tile {
+            x: 0,
+            y: 0,
+            width: 16,
+            height: 16,
+        };
+
+        random::reseed(7);
+        let expected: f64 = random::random();
+        random::reseed(7);
+        render_tile(&img_config, &shot, &|_, _| 2, 1, tile);
+        assert_eq!(random::random::<f64>(), expected);
+    }
+
+    #[test]
+    fn frames_and_passes_get_their_own_seeds() {
+        let mut seeds = Vec::new();
+        for seed in 0..4 {
+            for frame in 0..4 {
+                for pass in 0..4 {
+                    seeds.push(shot_seed(seed, frame, pass));
+                }
+            }
+        }
+        seeds.sort_unstable();
+        seeds.dedup();
+        assert_eq!(seeds.len(), 64);
+    }
+
+    #[test]
+    fn adaptive_samples_noisy_pixels() {
+        let (world, cameras, views) = sphere_scene();
+        let img_config = ImgConfig {
+            width: 20,
+            aspect_ratio: 1.0,
+            max_depth: 10,
+            adaptive: Some(AdaptiveConfig {
+                min_samples: 8,
+                max_samples: 64,
+                target_error: 0.05,
+                samples_filename: None,
+            }),
+            ..ImgConfig::default()
+        };
+
+        let shot = Shot {
+            world: &world,
+            cameras: &cameras,
+            views: &views,
+            shutter: (0.0, 0.0),
+            exposure: 1.0,
+        };
+        let film = render_frame(&img_config, &shot, 0, &Monitor::default()).unwrap();
+        // The sky is smooth, the diffuse sphere is not
+        assert_eq!(film.samples(0, 0), 8);
+        assert!(film.samples(10, 10) > 8);
+        assert!(film.samples(10, 10) <= 64);
+    }
+
+    #[test]
+    fn aovs_keep_the_image() {
+        let (world, cameras, views) = sphere_scene();
+        let shot = Shot {
+            world: &world,
+            cameras: &cameras,
+            views: &views,
+            shutter: (0.0, 0.0),
+            exposure: 1.0,
+        };
+        let img_config = |aovs| ImgConfig {
+            width: 21,
+            aspect_ratio: 1.0,
+            samples_per_pixel: 4,
+            max_depth: 5,
+            aovs,
+            ..ImgConfig::default()
+        };
+        let (plain, with_aovs) = (img_config(vec![]), img_config(vec![Aov::Depth]));
+
+        let film = render_frame(&with_aovs, &shot, 3, &Monitor::default()).unwrap();
+        assert_eq!(
+            film.to_rgb8(),
+            render_frame(&plain, &shot, 3, &Monitor::default())
+                .unwrap()
+                .to_rgb8()
+        );
+
+        // The middle pixel looks at the front of the sphere, 4 units away
+        let depth = film.aov(Aov::Depth).unwrap().pixel(10, 10);
+        assert!(float_eq!(depth[0], 4.0, 0.05), "{}", depth);
+        let normal = film.aov(Aov::Normal).unwrap().pixel(10, 10);
+        assert!(normal.z() > 0.95);
+        let albedo = film.aov(Aov::Albedo).unwrap().pixel(10, 10);
+        assert!(albedo.approx_eq(Color::new(0.5, 0.5, 0.5)));
+        // The top corner only sees the sky
+        assert!(film
+            .aov(Aov::Depth)
+            .unwrap()
+            .pixel(0, 0)
+            .approx_eq(Color::ceros()));
+        assert!(film
+            .aov(Aov::Emission)
+            .unwrap()
+            .pixel(0, 0)
+            .approx_eq(film.pixel(0, 0)));
+
+        let split = LIGHT_PATHS.iter().fold(Color::ceros(), |sum, &aov| {
+            sum + film.aov(aov).unwrap().pixel(10, 10)
+        });
+        assert!(split.approx_eq_epsilon(film.pixel(10, 10), 1e-12));
+    }
+
+    #[test]
+    fn denoising_lowers_error() {
+        let (world, cameras, views) = sphere_scene();
+        let shot = Shot {
+            world: &world,
+            cameras: &cameras,
+            views: &views,
+            shutter: (0.0, 0.0),
+            exposure: 1.0,
+        };
+        let img_config = |samples_per_pixel| ImgConfig {
+            width: 48,
+            aspect_ratio: 1.0,
+            samples_per_pixel,
+            max_depth: 5,
+            denoise: Some(crate::DenoiseConfig::default()),
+            ..ImgConfig::default()
+        };
+        let reference = render_frame(&img_config(256), &shot, 1, &Monitor::default())
+            .unwrap()
+            .to_colors();
+        let noisy = render_frame(&img_config(4), &shot, 2, &Monitor::default()).unwrap();
+        let denoised = denoise(&noisy, &crate::DenoiseConfig::default()).unwrap();
+
+        let error = |colors: &[Color]| -> f64 {
+            colors
+                .iter()
+                .zip(&reference)
+                .map(|(&a, &b)| (a - b).len2())
+                .sum()
+        };
+        let (before, after) = (error(&noisy.to_colors()), error(denoised.pixels()));
+        assert!(after < before / 2.0, "{} {}", before, after);
+    }
+}
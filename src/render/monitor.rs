@@ -0,0 +1,106 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// How a render is going, as given to `RunConfig::progress`. Tiles and
+/// pixels are counted over the current pass, which is the whole render
+/// unless it is progressive or adaptive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Progress {
+    pub tiles_done: usize,
+    pub tiles_total: usize,
+    pub pixels_done: u64,
+    pub pixels_total: u64,
+    /// Since the render started.
+    pub elapsed: Duration,
+    /// Until the current pass is done, guessed from how long the tiles so
+    /// far took.
+    pub eta: Duration,
+    /// Rays traced per second, counting every bounce.
+    pub rays_per_sec: f64,
+}
+
+/// Stops a render from another thread. Clones share the same state, and a
+/// cancelled render returns the film with the samples it got so far.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Progress callback and cancellation for one render, plus what is needed
+/// to report on it.
+pub(crate) struct Monitor<'a> {
+    progress: Option<&'a (dyn Fn(&Progress) + Sync)>,
+    cancel: Option<CancelToken>,
+    start: Instant,
+    rays: AtomicU64,
+}
+
+impl<'a> Monitor<'a> {
+    pub fn new(
+        progress: Option<&'a (dyn Fn(&Progress) + Sync)>,
+        cancel: Option<CancelToken>,
+    ) -> Self {
+        Self {
+            progress,
+            cancel,
+            start: Instant::now(),
+            rays: AtomicU64::new(0),
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|c| c.is_cancelled())
+    }
+
+    /// Adds the rays of a finished tile and tells the callback, `pass_start`
+    /// being when the current pass started.
+    pub fn tile_done(
+        &self,
+        rays: u64,
+        (tiles_done, tiles_total): (usize, usize),
+        (pixels_done, pixels_total): (u64, u64),
+        pass_start: Instant,
+    ) {
+        let rays = self.rays.fetch_add(rays, Ordering::Relaxed) + rays;
+        let progress = match self.progress {
+            Some(progress) => progress,
+            None => return,
+        };
+
+        let elapsed = self.start.elapsed();
+        let pass_elapsed = pass_start.elapsed().as_secs_f64();
+        let remaining = (pixels_total - pixels_done) as f64 / pixels_done.max(1) as f64;
+        progress(&Progress {
+            tiles_done,
+            tiles_total,
+            pixels_done,
+            pixels_total,
+            elapsed,
+            eta: Duration::from_secs_f64(pass_elapsed * remaining),
+            rays_per_sec: rays as f64 / elapsed.as_secs_f64().max(1e-9),
+        });
+    }
+}
+
+impl Default for Monitor<'_> {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
@@ -0,0 +1,301 @@
+use std::f64::consts::PI;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::prelude::*;
+
+use crate::{Color, Error, Image, PostEffect, Result};
+
+/// Applies `effects` in order to `image`, which should be in linear color,
+/// before the gamma correction of the display.
+pub fn post_process(image: &Image, effects: &[PostEffect]) -> Result<Image> {
+    let mut image = image.clone();
+    for effect in effects {
+        image = match *effect {
+            PostEffect::Bloom {
+                threshold,
+                radius,
+                intensity,
+            } => {
+                check(radius > 0.0, "Bloom needs a positive radius")?;
+                add(&image, &blur(&bright(&image, threshold), radius), intensity)
+            }
+            PostEffect::Glare {
+                threshold,
+                points,
+                length,
+                intensity,
+            } => {
+                check(
+                    points > 0 && length > 0.0,
+                    "Glare needs points and a positive length",
+                )?;
+                let star = star(&bright(&image, threshold), points, length);
+                add(&image, &star, intensity)
+            }
+            PostEffect::Vignette { strength } => {
+                check(
+                    (0.0..=1.0).contains(&strength),
+                    "Vignette strength must be between 0 and 1",
+                )?;
+                vignette(&image, strength)
+            }
+            PostEffect::Grain { amount, seed } => {
+                check(
+                    (0.0..=1.0).contains(&amount),
+                    "Grain amount must be between 0 and 1",
+                )?;
+                grain(&image, amount, seed)
+            }
+            PostEffect::ChromaticAberration { shift } => {
+                check(
+                    shift.abs() < 1.0,
+                    "Chromatic aberration shift must be between -1 and 1",
+                )?;
+                chromatic_aberration(&image, shift)
+            }
+        };
+    }
+    Ok(image)
+}
+
+fn check(valid: bool, msg: &str) -> Result<()> {
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidConfig(msg.to_string()))
+    }
+}
+
+/// Image of the same size with a color for each pixel.
+fn map_pixels(image: &Image, pixel: impl Fn(i64, i64) -> Color + Sync) -> Image {
+    let width = image.width() as i64;
+    let pixels = (0..width * image.height() as i64)
+        .into_par_iter()
+        .map(|p| pixel(p % width, p / width))
+        .collect();
+    Image::from_pixels(image.width(), image.height(), pixels)
+}
+
+/// Color at `(x, y)`, black outside the image.
+fn at(image: &Image, x: i64, y: i64) -> Color {
+    if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+        Color::ceros()
+    } else {
+        image.pixel(x as u32, y as u32)
+    }
+}
+
+fn add(image: &Image, glow: &Image, intensity: f64) -> Image {
+    map_pixels(image, |x, y| at(image, x, y) + at(glow, x, y) * intensity)
+}
+
+/// The light of each pixel above `threshold` luminance, keeping its hue.
+fn bright(image: &Image, threshold: f64) -> Image {
+    map_pixels(image, |x, y| {
+        let c = at(image, x, y);
+        let l = c.luminance();
+        if l > threshold {
+            c * ((l - threshold) / l)
+        } else {
+            Color::ceros()
+        }
+    })
+}
+
+/// Gaussian blur, losing the light that falls outside the image.
+fn blur(image: &Image, radius: f64) -> Image {
+    let reach = (3.0 * radius).ceil() as i64;
+    let weights: Vec<f64> = (-reach..=reach)
+        .map(|d| (-(d * d) as f64 / (2.0 * radius * radius)).exp())
+        .collect();
+    let total: f64 = weights.iter().sum();
+
+    let pass = |image: &Image, (dx, dy): (i64, i64)| {
+        map_pixels(image, |x, y| {
+            weights
+                .iter()
+                .zip(-reach..=reach)
+                .fold(Color::ceros(), |sum, (w, d)| {
+                    sum + at(image, x + d * dx, y + d * dy) * (w / total)
+                })
+        })
+    };
+    pass(&pass(image, (1, 0)), (0, 1))
+}
+
+/// Star of `points` streaks around each pixel, with exponential falloff.
+fn star(image: &Image, points: u32, length: f64) -> Image {
+    let reach = (4.0 * length).ceil() as u32;
+    let falloff: Vec<f64> = (1..=reach).map(|d| (-(d as f64) / length).exp()).collect();
+    let total = points as f64 * falloff.iter().sum::<f64>();
+    let directions: Vec<(f64, f64)> = (0..points)
+        .map(|k| {
+            let angle = 2.0 * PI * k as f64 / points as f64;
+            (angle.cos(), angle.sin())
+        })
+        .collect();
+
+    map_pixels(image, |x, y| {
+        let mut sum = Color::ceros();
+        for &(cos, sin) in directions.iter() {
+            for (d, w) in (1..=reach).zip(&falloff) {
+                let (qx, qy) = (
+                    x - (d as f64 * cos).round() as i64,
+                    y - (d as f64 * sin).round() as i64,
+                );
+                sum += at(image, qx, qy) * (w / total);
+            }
+        }
+        sum
+    })
+}
+
+/// Position of the center of `(x, y)` relative to the center of the image.
+fn from_center(image: &Image, x: f64, y: f64) -> (f64, f64) {
+    (
+        x + 0.5 - image.width() as f64 / 2.0,
+        y + 0.5 - image.height() as f64 / 2.0,
+    )
+}
+
+fn vignette(image: &Image, strength: f64) -> Image {
+    let (corner_x, corner_y) = from_center(image, -0.5, -0.5);
+    let corner2 = corner_x * corner_x + corner_y * corner_y;
+    map_pixels(image, |x, y| {
+        let (dx, dy) = from_center(image, x as f64, y as f64);
+        at(image, x, y) * (1.0 - strength * (dx * dx + dy * dy) / corner2)
+    })
+}
+
+fn grain(image: &Image, amount: f64, seed: u64) -> Image {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let pixels = image
+        .pixels()
+        .iter()
+        .map(|&c| c * (1.0 + amount * rng.gen_range(-1.0..=1.0)))
+        .collect();
+    Image::from_pixels(image.width(), image.height(), pixels)
+}
+
+/// Bilinear interpolation of `channel` at the image point `(x, y)`, taking
+/// the closest pixel past the edges.
+fn sample(image: &Image, channel: usize, x: f64, y: f64) -> f64 {
+    let max_x = image.width() as f64 - 1.0;
+    let max_y = image.height() as f64 - 1.0;
+    let (x, y) = ((x - 0.5).clamp(0.0, max_x), (y - 0.5).clamp(0.0, max_y));
+    let (x0, y0) = (x.floor(), y.floor());
+    let (x1, y1) = ((x0 + 1.0).min(max_x), (y0 + 1.0).min(max_y));
+    let (tx, ty) = (x - x0, y - y0);
+    let value = |x: f64, y: f64| image.pixel(x as u32, y as u32)[channel];
+    (value(x0, y0) * (1.0 - tx) + value(x1, y0) * tx) * (1.0 - ty)
+        + (value(x0, y1) * (1.0 - tx) + value(x1, y1) * tx) * ty
+}
+
+fn chromatic_aberration(image: &Image, shift: f64) -> Image {
+    let (center_x, center_y) = (image.width() as f64 / 2.0, image.height() as f64 / 2.0);
+    map_pixels(image, |x, y| {
+        let (dx, dy) = from_center(image, x as f64, y as f64);
+        let scaled = |channel, scale: f64| {
+            sample(image, channel, center_x + dx / scale, center_y + dy / scale)
+        };
+        Color::new(
+            scaled(0, 1.0 + shift),
+            at(image, x, y)[1],
+            scaled(2, 1.0 - shift),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Black 21x21 image with a bright white pixel in the middle.
+    fn spot() -> Image {
+        let mut pixels = vec![Color::ceros(); 21 * 21];
+        pixels[10 * 21 + 10] = Color::ones() * 10.0;
+        Image::new(21, 21, pixels).unwrap()
+    }
+
+    fn total(image: &Image) -> f64 {
+        image.pixels().iter().map(|&c| c.luminance()).sum()
+    }
+
+    #[test]
+    fn bloom_and_glare_spread_the_light() {
+        let bloom = PostEffect::Bloom {
+            threshold: 1.0,
+            radius: 2.0,
+            intensity: 0.5,
+        };
+        let glare = PostEffect::Glare {
+            threshold: 1.0,
+            points: 4,
+            length: 2.0,
+            intensity: 0.5,
+        };
+        for effect in [bloom, glare] {
+            let image = post_process(&spot(), &[effect]).unwrap();
+            // Half of the 9 units above the threshold, with little leaving
+            assert!(float_eq!(total(&image), 10.0 + 4.5, 1e-3), "{:?}", effect);
+            assert!(image.pixel(12, 10)[0] > 0.0);
+        }
+
+        // The glare only reaches along the streaks
+        let image = post_process(&spot(), &[glare]).unwrap();
+        assert!(image.pixel(14, 10)[0] > 0.0);
+        assert_eq!(image.pixel(14, 14)[0], 0.0);
+    }
+
+    #[test]
+    fn vignette_darkens_the_corners() {
+        let flat = Image::new(21, 21, vec![Color::ones(); 21 * 21]).unwrap();
+        let image = post_process(&flat, &[PostEffect::Vignette { strength: 0.4 }]).unwrap();
+        assert!(float_eq!(image.pixel(10, 10)[0], 1.0));
+        assert!(float_eq!(image.pixel(0, 0)[0], 0.64, 0.01));
+        assert!(image.pixel(0, 10)[0] > image.pixel(0, 0)[0]);
+
+        let too_strong = PostEffect::Vignette { strength: 2.0 };
+        assert!(post_process(&flat, &[too_strong]).is_err());
+    }
+
+    #[test]
+    fn grain_is_repeatable() {
+        let flat = Image::new(21, 21, vec![Color::ones(); 21 * 21]).unwrap();
+        let grain = |seed| {
+            let effect = PostEffect::Grain { amount: 0.1, seed };
+            post_process(&flat, &[effect]).unwrap()
+        };
+        let same = |a: Image, b: Image| {
+            a.pixels()
+                .iter()
+                .zip(b.pixels())
+                .all(|(&a, &b)| a.approx_eq(b))
+        };
+        assert!(same(grain(1), grain(1)));
+        assert!(!same(grain(1), grain(2)));
+        for c in grain(1).pixels() {
+            assert!((0.9..=1.1).contains(&c[0]));
+            assert!(c.approx_eq(Color::ones() * c[0]));
+        }
+    }
+
+    #[test]
+    fn chromatic_aberration_splits_colors() {
+        let mut pixels = vec![Color::ceros(); 21 * 21];
+        pixels[10 * 21 + 10] = Color::ones();
+        pixels[10 * 21 + 18] = Color::ones();
+        let image = Image::new(21, 21, pixels).unwrap();
+        let effect = PostEffect::ChromaticAberration { shift: 0.25 };
+        let split = post_process(&image, &[effect]).unwrap();
+
+        // Nothing moves at the center
+        assert!(split.pixel(10, 10).approx_eq(Color::ones()));
+        // Red spreads away from it and blue towards it
+        assert!(split.pixel(20, 10)[0] > 0.0);
+        assert_eq!(split.pixel(20, 10)[2], 0.0);
+        assert!(split.pixel(16, 10)[2] > 0.0);
+        assert_eq!(split.pixel(16, 10)[0], 0.0);
+        assert_eq!(split.pixel(18, 10)[1], 1.0);
+    }
+}
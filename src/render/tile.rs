@@ -0,0 +1,136 @@
+use crate::{Error, Result};
+
+/// Rectangle of the image rendered as one unit of work. `x` and `y` are its
+/// top left pixel, with rows counted from the top.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Order in which tiles are handed to the threads.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TileOrder {
+    /// Row by row from the top left corner.
+    Scanline,
+    /// Rings around the center of the image, which is usually where the
+    /// subject is.
+    Spiral,
+    /// Along a Hilbert curve, so consecutive tiles are always neighbours.
+    Hilbert,
+}
+
+/// Tiles of at most `size` by `size` pixels covering a `width` by `height`
+/// image, in `order`. Fails if `size` is 0.
+pub fn tiles(width: u32, height: u32, size: u32, order: TileOrder) -> Result<Vec<Tile>> {
+    if size == 0 {
+        return Err(Error::InvalidConfig("Tiles can't be empty".to_string()));
+    }
+    let cols = width.div_ceil(size);
+    let rows = height.div_ceil(size);
+
+    let mut cells: Vec<(u32, u32)> = (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (col, row)))
+        .collect();
+
+    match order {
+        TileOrder::Scanline => {}
+        TileOrder::Spiral => {
+            let center_col = (cols as f64 - 1.0) / 2.0;
+            let center_row = (rows as f64 - 1.0) / 2.0;
+            let key = |&(col, row): &(u32, u32)| {
+                let dx = col as f64 - center_col;
+                let dy = row as f64 - center_row;
+                let ring = dx.abs().max(dy.abs()).round();
+                (ring, dy.atan2(dx))
+            };
+            cells.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+        }
+        TileOrder::Hilbert => {
+            let n = cols.max(rows).next_power_of_two();
+            cells.sort_by_key(|&(col, row)| hilbert_index(n, col, row));
+        }
+    }
+
+    Ok(cells
+        .into_iter()
+        .map(|(col, row)| Tile {
+            x: col * size,
+            y: row * size,
+            width: size.min(width - col * size),
+            height: size.min(height - row * size),
+        })
+        .collect())
+}
+
+/// Distance along the Hilbert curve filling an `n` by `n` grid, with `n` a
+/// power of two, to the cell `(x, y)`.
+fn hilbert_index(n: u32, mut x: u32, mut y: u32) -> u64 {
+    let mut d = 0;
+    let mut s = n / 2;
+    while s > 0 {
+        let rx = (x & s > 0) as u32;
+        let ry = (y & s > 0) as u32;
+        d += s as u64 * s as u64 * ((3 * rx) ^ ry) as u64;
+
+        // Rotate the quadrant so the curve continues from the previous one
+        if ry == 0 {
+            if rx == 1 {
+                x = n - 1 - x;
+                y = n - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        s /= 2;
+    }
+    d
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn covers_image(tiles: &[Tile], width: u32, height: u32) -> bool {
+        let mut covered = vec![0; (width * height) as usize];
+        for tile in tiles {
+            for y in tile.y..tile.y + tile.height {
+                for x in tile.x..tile.x + tile.width {
+                    covered[(y * width + x) as usize] += 1;
+                }
+            }
+        }
+        covered.iter().all(|&c| c == 1)
+    }
+
+    #[test]
+    fn every_order_covers_image() {
+        for &order in [TileOrder::Scanline, TileOrder::Spiral, TileOrder::Hilbert].iter() {
+            let tiles = tiles(100, 70, 16, order).unwrap();
+            assert_eq!(tiles.len(), 7 * 5);
+            assert!(covers_image(&tiles, 100, 70));
+        }
+    }
+
+    #[test]
+    fn spiral_starts_at_center() {
+        let tiles = tiles(90, 90, 30, TileOrder::Spiral).unwrap();
+        assert_eq!((tiles[0].x, tiles[0].y), (30, 30));
+    }
+
+    #[test]
+    fn hilbert_neighbours() {
+        let tiles = tiles(128, 128, 16, TileOrder::Hilbert).unwrap();
+        for pair in tiles.windows(2) {
+            let dx = (pair[0].x as i64 - pair[1].x as i64).abs();
+            let dy = (pair[0].y as i64 - pair[1].y as i64).abs();
+            assert_eq!(dx + dy, 16);
+        }
+    }
+
+    #[test]
+    fn empty_tiles() {
+        assert!(tiles(10, 10, 0, TileOrder::Scanline).is_err());
+    }
+}
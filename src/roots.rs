@@ -0,0 +1,177 @@
+//! Real root finding for the low degree polynomials that show up in
+//! ray-surface intersections. Roots are returned in ascending order.
+
+use std::f64::consts::PI;
+
+const EPSILON: f64 = 1e-12;
+
+/// Solves `a x^2 + b x + c = 0`.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if float_eq_cero!(a, EPSILON) {
+        if float_eq_cero!(b, EPSILON) {
+            return Vec::new();
+        }
+        return vec![-c / b];
+    }
+
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return Vec::new();
+    }
+
+    // Avoids the cancellation of `-b + sqrt(disc)` when `b` dominates.
+    let q = -0.5 * (b + b.signum() * disc.sqrt());
+    let mut roots = if float_eq_cero!(q) {
+        vec![0.0]
+    } else {
+        vec![q / a, c / q]
+    };
+    roots.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    roots
+}
+
+/// Solves `x^3 + a x^2 + b x + c = 0`.
+fn solve_normalized_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let a_third = a / 3.0;
+    let q = (a * a - 3.0 * b) / 9.0;
+    let r = (2.0 * a * a * a - 9.0 * a * b + 27.0 * c) / 54.0;
+
+    let mut roots = if r * r < q * q * q {
+        let theta = (r / (q * q * q).sqrt()).clamp(-1.0, 1.0).acos();
+        let m = -2.0 * q.sqrt();
+        vec![
+            m * (theta / 3.0).cos() - a_third,
+            m * ((theta + 2.0 * PI) / 3.0).cos() - a_third,
+            m * ((theta - 2.0 * PI) / 3.0).cos() - a_third,
+        ]
+    } else {
+        let big_a = -r.signum() * (r.abs() + (r * r - q * q * q).sqrt()).cbrt();
+        let big_b = if float_eq_cero!(big_a) {
+            0.0
+        } else {
+            q / big_a
+        };
+        vec![big_a + big_b - a_third]
+    };
+    roots.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    roots
+}
+
+/// Solves `a x^3 + b x^2 + c x + d = 0`.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if float_eq_cero!(a, EPSILON) {
+        return solve_quadratic(b, c, d);
+    }
+
+    solve_normalized_cubic(b / a, c / a, d / a)
+}
+
+/// Solves `a x^4 + b x^3 + c x^2 + d x + e = 0` by reducing it to a depressed
+/// quartic and factoring it through a root of its resolvent cubic. The roots
+/// are refined with a few Newton steps on the original polynomial.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if float_eq_cero!(a, EPSILON) {
+        return solve_cubic(b, c, d, e);
+    }
+
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // x = y - b/4 gives y^4 + p y^2 + q y + r = 0
+    let b2 = b * b;
+    let p = c - 3.0 * b2 / 8.0;
+    let q = d - b * c / 2.0 + b2 * b / 8.0;
+    let r = e - b * d / 4.0 + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    let mut ys = Vec::new();
+    if float_eq_cero!(q, EPSILON) {
+        // Biquadratic
+        for z in solve_quadratic(1.0, p, r) {
+            if z >= 0.0 {
+                let s = z.sqrt();
+                ys.push(s);
+                ys.push(-s);
+            }
+        }
+    } else {
+        // Resolvent cubic: m^3 + p m^2 + (p^2/4 - r) m - q^2/8 = 0 has a
+        // positive root that splits the quartic into two quadratics.
+        let m = solve_normalized_cubic(p, p * p / 4.0 - r, -q * q / 8.0)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0.0 {
+            return Vec::new();
+        }
+
+        let s = (2.0 * m).sqrt();
+        ys.extend(solve_quadratic(1.0, s, p / 2.0 + m - q / (2.0 * s)));
+        ys.extend(solve_quadratic(1.0, -s, p / 2.0 + m + q / (2.0 * s)));
+    }
+
+    let poly = |x: f64| (((x + b) * x + c) * x + d) * x + e;
+    let deriv = |x: f64| ((4.0 * x + 3.0 * b) * x + 2.0 * c) * x + d;
+
+    let mut roots: Vec<f64> = ys
+        .into_iter()
+        .map(|y| {
+            let mut x = y - b / 4.0;
+            for _ in 0..4 {
+                let dp = deriv(x);
+                if float_eq_cero!(dp, EPSILON) {
+                    break;
+                }
+                x -= poly(x) / dp;
+            }
+            x
+        })
+        .collect();
+    roots.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(found: Vec<f64>, expected: &[f64]) {
+        assert_eq!(found.len(), expected.len(), "{:?}", found);
+        for (f, e) in found.iter().zip(expected.iter()) {
+            assert!(float_eq!(f, e, 1e-9), "{:?} != {:?}", found, expected);
+        }
+    }
+
+    #[test]
+    fn quadratic() {
+        assert_roots(solve_quadratic(1.0, -3.0, 2.0), &[1.0, 2.0]);
+        assert_roots(solve_quadratic(0.0, 2.0, -4.0), &[2.0]);
+        assert_roots(solve_quadratic(1.0, 0.0, 1.0), &[]);
+    }
+
+    #[test]
+    fn cubic() {
+        // 2(x + 1)(x - 1)(x - 3)
+        assert_roots(solve_cubic(2.0, -6.0, -2.0, 6.0), &[-1.0, 1.0, 3.0]);
+        // (x - 2)(x^2 + 1)
+        assert_roots(solve_cubic(1.0, -2.0, 1.0, -2.0), &[2.0]);
+        assert_roots(solve_cubic(0.0, 1.0, -3.0, 2.0), &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(
+            solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        // (x^2 - 1)(x^2 - 4)
+        assert_roots(
+            solve_quartic(2.0, 0.0, -10.0, 0.0, 8.0),
+            &[-2.0, -1.0, 1.0, 2.0],
+        );
+        // (x - 1)(x + 2)(x^2 + 1)
+        assert_roots(solve_quartic(1.0, 1.0, -1.0, 1.0, -2.0), &[-2.0, 1.0]);
+        // x^4 + 1
+        assert_roots(solve_quartic(1.0, 0.0, 0.0, 0.0, 1.0), &[]);
+        // No quartic term left: (x - 1)(x - 2)(x - 3)
+        assert_roots(solve_quartic(0.0, 1.0, -6.0, 11.0, -6.0), &[1.0, 2.0, 3.0]);
+    }
+}
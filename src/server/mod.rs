@@ -0,0 +1,233 @@
+use std::{
+    collections::HashMap,
+    io::BufReader,
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::{job::Job, Result};
+
+mod http;
+mod render_job;
+
+use http::{read_request, Request, Response};
+use render_job::RenderJob;
+
+/// Clients that go this long without sending anything are dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Jobs by id, and the id of the next one. Ids start at 1 and aren't
+/// reused after a job is gone.
+#[derive(Default)]
+struct JobTable {
+    jobs: HashMap<usize, Arc<RenderJob>>,
+    next_id: usize,
+}
+
+type Jobs = Mutex<JobTable>;
+
+/// Serves the render API on `listener`, each request on its own thread.
+///
+/// - `POST /jobs` with a job description (as sent to distributed workers)
+///   starts a render, and answers with its id.
+/// - `GET /jobs/<id>` tells its state, samples so far and progress.
+/// - `GET /jobs/<id>/preview.png` is the image so far.
+/// - `GET /jobs/<id>/result.hdr` is the final image, once done.
+/// - `DELETE /jobs/<id>` cancels it.
+///
+/// A job is forgotten once its result is fetched or it is cancelled.
+pub fn serve(listener: TcpListener) -> Result<()> {
+    let jobs: Arc<Jobs> = Arc::default();
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let jobs = jobs.clone();
+        thread::spawn(move || handle(stream, &jobs));
+    }
+    Ok(())
+}
+
+fn handle(stream: TcpStream, jobs: &Jobs) {
+    if stream.set_read_timeout(Some(READ_TIMEOUT)).is_err() {
+        return;
+    }
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+    let response = match read_request(&mut BufReader::new(stream)) {
+        Ok(request) => route(&request, jobs),
+        Err(e) => Response::error(400, &e.to_string()),
+    };
+    // The client is gone, nobody to tell
+    let _ = response.write_to(&mut writer);
+}
+
+fn route(request: &Request, jobs: &Jobs) -> Response {
+    let segments: Vec<&str> = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["jobs"]) => {
+            let job = match std::str::from_utf8(&request.body)
+                .map_err(|_| "The job description isn't UTF-8".to_string())
+                .and_then(|text| Job::parse(text).map_err(|e| e.to_string()))
+            {
+                Ok(job) => job,
+                Err(msg) => return Response::error(400, &msg),
+            };
+
+            let render = RenderJob::start(job);
+            let mut table = jobs.lock().unwrap();
+            table.next_id += 1;
+            let id = table.next_id;
+            table.jobs.insert(id, render);
+            Response::json(201, format!("{{\"id\":{}}}", id))
+        }
+        (method, ["jobs", id, rest @ ..]) => {
+            let (id, job) = match id.parse::<usize>().ok().and_then(|id| {
+                let table = jobs.lock().unwrap();
+                table.jobs.get(&id).map(|job| (id, job.clone()))
+            }) {
+                Some(found) => found,
+                None => return Response::error(404, "No such job"),
+            };
+            let forget = || jobs.lock().unwrap().jobs.remove(&id);
+
+            match (method, rest) {
+                ("GET", []) => Response::json(200, job.status_json(id)),
+                ("DELETE", []) => {
+                    // The render stops on its own after the tiles it's on
+                    job.cancel();
+                    forget();
+                    Response::json(200, job.status_json(id))
+                }
+                ("GET", ["preview.png"]) => Response::new(200, "image/png", job.preview_png()),
+                ("GET", ["result.hdr"]) => match job.result_hdr() {
+                    Some(hdr) => {
+                        forget();
+                        Response::new(200, "image/vnd.radiance", hdr)
+                    }
+                    None => Response::error(409, "The render isn't done"),
+                },
+                (_, []) | (_, ["preview.png"]) | (_, ["result.hdr"]) => {
+                    Response::error(405, "Method not allowed")
+                }
+                _ => Response::error(404, "Not found"),
+            }
+        }
+        _ => Response::error(404, "Not found"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::SocketAddr,
+        time::Duration,
+    };
+
+    fn start() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(listener));
+        addr
+    }
+
+    fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Vec<u8>) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        let end = response.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8_lossy(&response[..end]).to_string();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, response[end + 4..].to_vec())
+    }
+
+    fn status(addr: SocketAddr, id: &str) -> String {
+        let (code, body) = request(addr, "GET", &format!("/jobs/{}", id), "");
+        assert_eq!(code, 200);
+        String::from_utf8(body).unwrap()
+    }
+
+    fn wait_while_rendering(addr: SocketAddr, id: &str) -> String {
+        for _ in 0..1000 {
+            let status = status(addr, id);
+            if !status.contains("\"rendering\"") {
+                return status;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("The render never finished");
+    }
+
+    const JOB: &str = "width 16\naspect_ratio 2\nsamples_per_pixel 6\nmax_depth 4\n\
+                       small_sphere_count 5\nscene_seed 1\n";
+
+    #[test]
+    fn render_and_fetch() {
+        let addr = start();
+
+        let (code, body) = request(addr, "POST", "/jobs", JOB);
+        assert_eq!(code, 201);
+        assert_eq!(body, b"{\"id\":1}");
+
+        let status = wait_while_rendering(addr, "1");
+        assert!(status.contains("\"state\":\"done\""), "{}", status);
+        assert!(status.contains("\"samples\":6"), "{}", status);
+
+        let (code, png) = request(addr, "GET", "/jobs/1/preview.png", "");
+        assert_eq!(code, 200);
+        assert_eq!(&png[..4], b"\x89PNG");
+        let (code, hdr) = request(addr, "GET", "/jobs/1/result.hdr", "");
+        assert_eq!(code, 200);
+        assert!(hdr.starts_with(b"#?RADIANCE"));
+        assert!(hdr.len() > 16 * 8 * 4);
+
+        // Fetching the result frees the job, and ids aren't reused
+        assert_eq!(request(addr, "GET", "/jobs/1", "").0, 404);
+        let (code, body) = request(addr, "POST", "/jobs", JOB);
+        assert_eq!(code, 201);
+        assert_eq!(body, b"{\"id\":2}");
+    }
+
+    #[test]
+    fn cancel_and_errors() {
+        let addr = start();
+
+        let long = JOB.replace("samples_per_pixel 6", "samples_per_pixel 100000");
+        request(addr, "POST", "/jobs", &long);
+        let (code, _) = request(addr, "GET", "/jobs/1/result.hdr", "");
+        assert_eq!(code, 409);
+        assert_eq!(request(addr, "PUT", "/jobs/1", "").0, 405);
+        let (code, _) = request(addr, "DELETE", "/jobs/1", "");
+        assert_eq!(code, 200);
+        assert_eq!(request(addr, "GET", "/jobs/1", "").0, 404);
+        assert_eq!(request(addr, "DELETE", "/jobs/1", "").0, 404);
+
+        assert_eq!(request(addr, "POST", "/jobs", "width many").0, 400);
+        assert_eq!(
+            request(addr, "POST", "/jobs", "width 100000\naspect_ratio 0.001").0,
+            400
+        );
+        assert_eq!(request(addr, "GET", "/jobs/7", "").0, 404);
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    build_world, image_io,
+    job::Job,
+    random_scene,
+    render::{render_pass, shot_seed, CancelToken, Film, Monitor, Shot},
+    Result,
+};
+
+/// Samples added to every pixel between updates of the preview.
+const SAMPLES_PER_PASS: u32 = 4;
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum State {
+    Rendering,
+    Done,
+    Cancelled,
+    Failed(String),
+}
+
+/// Render running in the background for the server, in passes so the
+/// preview and the progress can be checked while it goes.
+pub(crate) struct RenderJob {
+    state: Mutex<State>,
+    film: Mutex<Film>,
+    samples: AtomicU32,
+    target: u32,
+    cancel: CancelToken,
+}
+
+impl RenderJob {
+    pub fn start(job: Job) -> Arc<Self> {
+        let render = Arc::new(Self {
+            state: Mutex::new(State::Rendering),
+            film: Mutex::new(Film::new(job.img_config.width, job.img_config.height())),
+            samples: AtomicU32::new(0),
+            target: job.img_config.samples_per_pixel,
+            cancel: CancelToken::new(),
+        });
+
+        let background = render.clone();
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| background.render(&job)));
+            let mut state = background.state.lock().unwrap();
+            *state = match result {
+                Ok(Ok(())) if background.cancel.is_cancelled() => State::Cancelled,
+                Ok(Ok(())) => State::Done,
+                Ok(Err(e)) => State::Failed(e.to_string()),
+                Err(e) => State::Failed(
+                    e.downcast_ref::<&str>()
+                        .map(|s| s.to_string())
+                        .or_else(|| e.downcast_ref::<String>().cloned())
+                        .unwrap_or_else(|| "Render failed".to_string()),
+                ),
+            };
+        });
+
+        render
+    }
+
+    fn render(&self, job: &Job) -> Result<()> {
+        let Job {
+            img_config,
+            cam_config,
+            scene_config,
+            use_bvh,
+        } = job;
+        let world = build_world(random_scene(scene_config)?, *use_bvh)?;
+        let cameras = [cam_config.camera(img_config.aspect_ratio, &*world, 0.0)?];
+        let shot = Shot {
+            world: &world,
+            cameras: &cameras,
+            views: &[cam_config.view()],
+            shutter: (0.0, 0.0),
+            exposure: cam_config.exposure(),
+        };
+        let monitor = Monitor::new(None, Some(self.cancel.clone()));
+
+        let mut pass = 0;
+        let mut samples = 0;
+        while samples < self.target && !monitor.is_cancelled() {
+            let pass_samples = SAMPLES_PER_PASS.min(self.target - samples);
+            let mut pass_film = Film::new(img_config.width, img_config.height());
+            render_pass(
+                img_config,
+                &shot,
+                &|_, _| pass_samples,
+                shot_seed(img_config.seed, 0, pass),
+                &mut pass_film,
+                &monitor,
+            )?;
+            if monitor.is_cancelled() {
+                // The pass stopped partway, and only whole passes are kept
+                break;
+            }
+
+            self.film.lock().unwrap().merge(&pass_film, 0, 0)?;
+            samples += pass_samples;
+            pass += 1;
+            self.samples.store(samples, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    pub fn state(&self) -> State {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Stops the render once the tiles being rendered are done.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    pub fn status_json(&self, id: usize) -> String {
+        let samples = self.samples.load(Ordering::Relaxed);
+        let (state, error) = match self.state() {
+            State::Rendering => ("rendering", None),
+            State::Done => ("done", None),
+            State::Cancelled => ("cancelled", None),
+            State::Failed(msg) => ("failed", Some(msg)),
+        };
+        let error = error
+            .map(|msg| {
+                format!(
+                    ",\"error\":\"{}\"",
+                    msg.replace('\\', "\\\\").replace('"', "\\\"")
+                )
+            })
+            .unwrap_or_default();
+
+        format!(
+            "{{\"id\":{},\"state\":\"{}\",\"samples\":{},\"target\":{},\"progress\":{}{}}}",
+            id,
+            state,
+            samples,
+            self.target,
+            samples as f64 / self.target.max(1) as f64,
+            error
+        )
+    }
+
+    /// PNG of the image so far.
+    pub fn preview_png(&self) -> Vec<u8> {
+        let film = self.film.lock().unwrap();
+        let mut png = Vec::new();
+        image_io::write_png(&mut png, film.width(), film.height(), &film.to_rgb8())
+            .expect("Writing to memory can't fail");
+        png
+    }
+
+    /// Radiance HDR image, once the render is done.
+    pub fn result_hdr(&self) -> Option<Vec<u8>> {
+        if self.state() != State::Done {
+            return None;
+        }
+
+        let film = self.film.lock().unwrap();
+        let mut hdr = Vec::new();
+        image_io::write_hdr(&mut hdr, film.width(), film.height(), &film.to_colors())
+            .expect("Writing to memory can't fail");
+        Some(hdr)
+    }
+}
@@ -1,9 +1,12 @@
 use std::{
+    convert::TryFrom,
     fmt,
     ops::{Add, AddAssign, Div, DivAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign},
 };
 
-use rand::{thread_rng, Rng};
+use rand::Rng;
+
+use crate::{random::with_rng, Error};
 
 #[derive(Clone, Copy)]
 pub struct Vec3 {
@@ -43,18 +46,16 @@ impl Vec3 {
     }
 
     pub fn random_in_unit_cube() -> Self {
-        let mut rng = thread_rng();
-        Self {
+        with_rng(|rng| Self {
             e: [rng.gen(), rng.gen(), rng.gen()],
-        }
+        })
     }
 
     pub fn random_in_range(min: f64, max: f64) -> Self {
-        let mut rng = thread_rng();
         let distr = rand::distributions::Uniform::new(min, max);
-        Self {
+        with_rng(|rng| Self {
             e: [rng.sample(distr), rng.sample(distr), rng.sample(distr)],
-        }
+        })
     }
 
     pub fn random_in_unit_sphere() -> Self {
@@ -102,6 +103,11 @@ impl Vec3 {
         get_color_value(self[2], samples)
     }
 
+    /// Rec. 709 luminance of the color.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self[0] + 0.7152 * self[1] + 0.0722 * self[2]
+    }
+
     pub fn len2(&self) -> f64 {
         self.e.iter().fold(0.0, |a, v| v * v + a)
     }
@@ -298,17 +304,37 @@ impl DivAssign<f64> for Vec3 {
     }
 }
 
-impl From<&str> for Vec3 {
-    fn from(s: &str) -> Self {
-        let values: Vec<f64> = s
+impl std::str::FromStr for Vec3 {
+    type Err = Error;
+
+    /// Parses three numbers separated by whitespace, as written by `Display`.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let values = s
             .split_ascii_whitespace()
-            .map(|v| v.parse().expect("Invalid float literal in string"))
-            .collect();
-        assert!(values.len() == 3);
+            .map(|v| {
+                v.parse()
+                    .map_err(|_| Error::Parse(format!("Invalid number '{}' in vector", v)))
+            })
+            .collect::<Result<Vec<f64>, Error>>()?;
+        if values.len() != 3 {
+            return Err(Error::Parse(format!(
+                "A vector needs 3 numbers, found {} in '{}'",
+                values.len(),
+                s
+            )));
+        }
 
-        Self {
+        Ok(Self {
             e: [values[0], values[1], values[2]],
-        }
+        })
+    }
+}
+
+impl TryFrom<&str> for Vec3 {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Error> {
+        s.parse()
     }
 }
 
@@ -357,7 +383,7 @@ mod tests {
     #[test]
     fn approx_eq() {
         let v1 = Vec3::new(1.0, 2.0, 3.0);
-        let v2 = v1.clone();
+        let v2 = v1;
 
         assert!(v1.approx_eq(v2));
 
@@ -521,11 +547,14 @@ mod tests {
     }
 
     #[test]
-    fn from() {
+    fn from_str() {
         let vec = Vec3::new(1.0, 2.0, 3.0);
         let s = "1 2 3";
 
-        assert!(vec.approx_eq(Vec3::from(s)));
+        assert!(vec.approx_eq(s.parse().unwrap()));
+        assert!(vec.approx_eq(Vec3::try_from(s).unwrap()));
+        assert!(matches!("1 2 x".parse::<Vec3>(), Err(Error::Parse(_))));
+        assert!(matches!(Vec3::try_from("1 2"), Err(Error::Parse(_))));
     }
 
     #[test]